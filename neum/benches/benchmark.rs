@@ -26,12 +26,19 @@ fn parsing_files(c: &mut Criterion) {
     });
 }
 
+/// A copy of `neum` with an empty cache, clones share the cache so each run needs its own
+fn fresh(neum: &Neum) -> Neum {
+    let mut neum = neum.clone();
+    neum.refresh();
+    neum
+}
+
 fn convert(c: &mut Criterion) {
     let default = Neum::default();
     c.bench_function("convert", |b| {
         b.iter_batched(
-            || default.clone(),
-            |mut default| {
+            || fresh(&default),
+            |default| {
                 for i in vec![
                     "m-0",
                     "ds-lg",
@@ -68,6 +75,42 @@ fn convert(c: &mut Criterion) {
     });
 }
 
+fn convert_large(c: &mut Criterion) {
+    let default = Neum::default();
+    let mut classes = Vec::new();
+    for prefix in [
+        "m", "mt", "mb", "ml", "mr", "p", "pt", "pb", "pl", "pr", "w", "h", "max-w", "min-h", "fs",
+        "lh", "r", "top", "left",
+    ] {
+        for i in 0..50 {
+            classes.push(format!("{prefix}-{i}"));
+            classes.push(format!("{prefix}-{i}px"));
+        }
+    }
+    for color in [
+        "red", "blue", "teal", "white", "black", "gray", "lime", "navy",
+    ] {
+        for prefix in ["bg", "c", "bc", "outline"] {
+            classes.push(format!("{prefix}-{color}"));
+        }
+    }
+    for i in 0..200 {
+        classes.push(format!("not-a-class-{i}"));
+    }
+
+    c.bench_function("convert large", |b| {
+        b.iter_batched(
+            || fresh(&default),
+            |default| {
+                for i in &classes {
+                    default.convert(i);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
 fn init(c: &mut Criterion) {
     c.bench_function("initilize default", |b| {
         b.iter(|| {
@@ -134,5 +177,13 @@ fn combine(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, parsing_files, convert, init, add, combine);
+criterion_group!(
+    benches,
+    parsing_files,
+    convert,
+    convert_large,
+    init,
+    add,
+    combine
+);
 criterion_main!(benches);
@@ -1,5 +1,6 @@
 use neum_parse::{
-    lexer::{self, Token},
+    ast::{Expr, Node, NodeKind, Rule},
+    lexer,
     parse::{self, Name},
 };
 
@@ -11,13 +12,15 @@ use std::io::{BufWriter, Write};
 use std::path::Path;
 
 fn main() {
-    let mut total: Vec<(Name, Vec<Token>)> = Vec::new();
+    let mut total: Vec<(Name, Rule)> = Vec::new();
     let out_dir = env::var("OUT_DIR").unwrap();
     let output = Path::new(&out_dir).join("formated.rs");
     let mut file = BufWriter::new(File::create(&output).unwrap());
     let mut files = Vec::new();
-    let mut consts: HashMap<String, Vec<Token>> = HashMap::new();
-    for i in walkdir::WalkDir::new(Path::new("src/default")) {
+    let mut consts: HashMap<String, Rule> = HashMap::new();
+    let mut variants: HashMap<String, Rule> = HashMap::new();
+    let mut sources = String::new();
+    for i in walkdir::WalkDir::new(Path::new("src/default")).sort_by_file_name() {
         let i = i
             .as_ref()
             .unwrap_or_else(|_| panic!("Cant get a file, {i:?}"));
@@ -26,6 +29,7 @@ fn main() {
             files.push(i.clone());
             let content = fs::read_to_string(file.clone())
                 .unwrap_or_else(|_| panic!("Cant read the contents of {file}"));
+            sources.push_str(&format!("({file:?}, {content:?}),"));
             let output = parse::parse(
                 lexer::lex(Some(&file.clone()), &content.clone()).unwrap(),
                 Some(&file),
@@ -35,64 +39,49 @@ fn main() {
             let mut tokens = output.dynamics;
             total.append(&mut tokens);
             consts.extend(output.statics);
+            variants.extend(output.variants);
         }
     }
     let mut text = String::new();
     for i in total {
         text.push_str(&format!(
-            "(Name {{ regex: Arc::new(Regex::new(r\"{}\").unwrap()), variables: Arc::new(vec![{}]) }}, vec![{}]),",
+            "(Name {{ regex: Arc::new(Regex::new(r\"{}\").unwrap()), variables: Arc::new(vec![{}]), specificity: {:?} }}, {}),",
             i.0.regex,
             i.0.variables
                 .iter()
                 .map(|x| format!("{x:?}.to_string()"))
                 .collect::<Vec<_>>()
                 .join(","),
-            {
-                let mut tokens = String::new();
-                for i in i.1 {
-                    tokens.push_str(
-                        match i {
-                            Token::String(x) => format!("String({x:?}.to_string()),"),
-                            _ => format!("{i:?},"),
-                        }
-                        .as_str(),
-                    );
-                }
-                tokens
-            }
+            i.0.specificity,
+            rule(&i.1)
         ));
     }
     let mut consts_text = String::new();
     for (x, y) in consts.iter() {
-        consts_text.push_str(&format!("(\"{x}\".to_string(), vec![{}]),", {
-            let mut tokens = String::new();
-            for i in y {
-                tokens.push_str(
-                    match i {
-                        Token::String(x) => format!("String({x:?}.to_string()),"),
-                        _ => format!("{i:?},"),
-                    }
-                    .as_str(),
-                );
-            }
-            tokens
-        }));
+        consts_text.push_str(&format!("({x:?}.to_string(), {}),", rule(y)));
+    }
+    let mut variants_text = String::new();
+    for (x, y) in variants.iter() {
+        variants_text.push_str(&format!("({x:?}.to_string(), {}),", rule(y)));
     }
     writeln!(
         &mut file,
-        "use neum_parse::{{parse::{{*}}, lexer::Token::*}};
+        "use neum_parse::{{parse::*, ast::*}};
 use regex::Regex;
 
 impl Default for Neum {{
     /// A Neum object with the default values
     /// ```no_run
     /// # use neum::Neum;
-    /// assert_eq!(Neum::default().convert(\"w-50%\"), Some(String::from(\"width:50%;\")));
+    /// assert_eq!(Neum::default().convert(\"w-50%\"), Some(String::from(\"width: 50%;\")));
     /// ```
     fn default() -> Self {{
-        Neum {{ converts: Arc::new(vec![{text}]), consts: Arc::new([{consts_text}].into_iter().collect()), cache: Arc::new(hashbrown::HashMap::new()) }}
+        Neum {{ converts: Arc::new(vec![{text}]), consts: Arc::new([{consts_text}].into_iter().collect()), variants: Arc::new([{variants_text}].into_iter().collect()), cache: Arc::new(neum_parse::cache::Cache::new()), options: Options::default(), matcher: Arc::new(std::sync::OnceLock::new()) }}
     }}
 }}
+
+/// The files the defaults are defined in, named the same as the file of their rules, with what is in each one
+pub const DEFAULT_SOURCES: &[(&str, &str)] = &[{sources}];
 "
     )
     .expect("Cant write to file");
@@ -153,3 +142,67 @@ pub fn {}() {{}}
         .expect("Cant write to file");
     }
 }
+
+fn rule(rule: &Rule) -> String {
+    format!(
+        "Rule {{ name: {:?}.to_string(), body: {}, span: {:?}, file: {}, line: {} }}",
+        rule.name,
+        nodes(&rule.body),
+        rule.span,
+        match &rule.file {
+            Some(x) => format!("Some({x:?}.to_string())"),
+            None => "None".to_string(),
+        },
+        rule.line
+    )
+}
+
+fn nodes(nodes: &[Node]) -> String {
+    format!(
+        "vec![{}]",
+        nodes
+            .iter()
+            .map(|x| format!("Node {{ kind: {}, span: {:?} }}", kind(&x.kind), x.span))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+fn kind(kind: &NodeKind) -> String {
+    match kind {
+        NodeKind::Text(x) => format!("NodeKind::Text({x:?}.to_string())"),
+        NodeKind::Variable(x) => format!("NodeKind::Variable({x:?}.to_string())"),
+        NodeKind::Expression(x, unit) => format!(
+            "NodeKind::Expression({}, {})",
+            expr(x),
+            match unit {
+                Some(x) => format!("Some({x:?}.to_string())"),
+                None => "None".to_string(),
+            }
+        ),
+        NodeKind::Lookup(x) => format!("NodeKind::Lookup({})", nodes(x)),
+        NodeKind::Block(x) => format!("NodeKind::Block({})", nodes(x)),
+    }
+}
+
+fn expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(x) => format!("Expr::Number({x:?})"),
+        Expr::Dimension(x, unit) => format!("Expr::Dimension({x:?}, {unit:?}.to_string())"),
+        Expr::Variable(x) => format!("Expr::Variable({x:?}.to_string())"),
+        Expr::Negate(x) => format!("Expr::Negate(Box::new({}))", self::expr(x)),
+        Expr::Binary(left, operator, right) => format!(
+            "Expr::Binary(Box::new({}), Operator::{operator:?}, Box::new({}))",
+            self::expr(left),
+            self::expr(right)
+        ),
+        Expr::Function(function, arguments) => format!(
+            "Expr::Function(Function::{function:?}, vec![{}])",
+            arguments
+                .iter()
+                .map(self::expr)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    }
+}
@@ -1 +1,22 @@
+//! The classes made from the defaults are escaped in their selectors, the same as `CSS.escape`
+//! does, for each character that they use
+//! ```
+//! # use neum::Neum;
+//! let neum = Neum::default();
+//! assert_eq!(neum.convert("hover:w-50%"), Some(String::from(r".hover\:w-50\%:hover{width:50%}")));
+//! assert_eq!(neum.convert("hover:h-6.5"), Some(String::from(r".hover\:h-6\.5:hover{height:1.625rem}")));
+//! assert_eq!(neum.convert("focus:w-1/2"), Some(String::from(r".focus\:w-1\/2:focus{width:50%}")));
+//! assert_eq!(neum.convert("hover:mt--4"), Some(String::from(r".hover\:mt--4:hover{margin-top:-1rem}")));
+//! assert_eq!(neum.convert("hover:bg-[#ff0000]"), Some(String::from(r".hover\:bg-\[\#ff0000\]:hover{background-color:#ff0000}")));
+//! assert_eq!(neum.convert("md:w-1/2"), Some(String::from(r"@media (min-width:768px){.md\:w-1\/2{width:50%}}")));
+//! ```
+//!
+//! The classes for both sides look up the class for each side, each one being its own declaration
+//! ```
+//! # use neum::Neum;
+//! let neum = Neum::default();
+//! assert_eq!(neum.convert("mx-2"), Some(String::from("margin-left:0.5rem;margin-right:0.5rem")));
+//! assert_eq!(neum.convert("my-1"), Some(String::from("margin-top:0.25rem;margin-bottom:0.25rem")));
+//! ```
+
 // include!(concat!(env!("OUT_DIR"), "/definitions.rs"));
@@ -1,3 +1,4 @@
+use crate::config;
 use clap::{Parser, Subcommand};
 use lazy_static::lazy_static;
 use std::path::PathBuf;
@@ -9,6 +10,21 @@ pub struct Args {
     #[command(subcommand)]
     pub command: Option<Commands>,
 
+    #[command(flatten)]
+    pub options: Options,
+
+    /// Automatically look for files to change then update your outputed css (setting this will make it not watch)
+    #[clap(short, long, value_parser, default_value_t = true, action=clap::ArgAction::SetFalse, overrides_with = "watching")]
+    pub watch: bool,
+
+    /// Watch for changes even if neum.toml sets `watch = false`
+    #[clap(long, value_parser, default_value_t = false, overrides_with = "watch")]
+    pub watching: bool,
+}
+
+/// How the css is made from the source code
+#[derive(clap::Args)]
+pub struct Options {
     /// This is the path to where your html and javascript is
     #[clap(short, long, value_parser)]
     pub source_code: Option<PathBuf>,
@@ -21,23 +37,132 @@ pub struct Args {
     #[clap(short, long, value_parser)]
     pub exclude: Vec<PathBuf>,
 
-    /// Your output css file
-    #[clap(short, long, value_parser, required = true)]
-    pub output: Option<PathBuf>,
+    /// Your output css file, this can be given more than once to write the same css to each
+    #[clap(short, long, value_parser)]
+    pub output: Vec<PathBuf>,
 
     /// Show extra information
-    #[clap(short, long, value_parser, default_value_t = false)]
+    #[clap(
+        short,
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "no_verbose"
+    )]
     pub verbose: bool,
 
-    /// Automatically look for files to change then update your outputed css (setting this will make it not watch)
-    #[clap(short, long, value_parser, default_value_t = true, action=clap::ArgAction::SetFalse)]
-    pub watch: bool,
+    /// Do not show extra information even if neum.toml turns it on
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "verbose"
+    )]
+    pub no_verbose: bool,
+
+    /// Use the most specific definition for a class instead of the one with the highest priority
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "no_specific"
+    )]
+    pub specific: bool,
+
+    /// Use the definition with the highest priority even if neum.toml sets `specific`
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "specific"
+    )]
+    pub no_specific: bool,
+
+    /// Warn about classes that more than one definition could be used for
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "no_ambiguous"
+    )]
+    pub ambiguous: bool,
+
+    /// Do not warn about ambiguous classes even if neum.toml turns it on
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "ambiguous"
+    )]
+    pub no_ambiguous: bool,
+
+    /// Print the classes that did not match any definition, the files they are used in and similar classes
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "no_report"
+    )]
+    pub report: bool,
+
+    /// Do not print the classes that did not match even if neum.toml turns it on
+    #[clap(long, value_parser, default_value_t = false, overrides_with = "report")]
+    pub no_report: bool,
+
+    /// Write the classes that did not match any definition to a json file
+    #[clap(long, value_parser)]
+    pub report_json: Option<PathBuf>,
+
+    /// Keep at most this many converted classes in memory, 0 turns the cache off, by default every class is kept
+    #[clap(long, value_parser)]
+    pub cache_size: Option<usize>,
+
+    /// A class that is always made even if it is not used, `{a,b}` is expanded to each choice, `{1..3}` to each
+    /// number and `/regex/` is every class without placeholders it matches, this can be given more than once
+    #[clap(long, value_parser)]
+    pub safelist: Vec<String>,
+
+    /// A class that is never made even if it is used, written the same as for --safelist
+    #[clap(long, value_parser)]
+    pub blocklist: Vec<String>,
+
+    /// Write the css with nothing that is not needed, this is the default
+    #[clap(long, value_parser, default_value_t = false, overrides_with = "pretty")]
+    pub minify: bool,
+
+    /// Write the css with each rule and declaration on its own line
+    #[clap(long, value_parser, default_value_t = false, overrides_with = "minify")]
+    pub pretty: bool,
+
+    /// Write a source map next to each output so browsers can show the neum rule each declaration came from
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "no_source_map"
+    )]
+    pub source_map: bool,
+
+    /// Do not write a source map even if neum.toml turns it on
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "source_map"
+    )]
+    pub no_source_map: bool,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     #[cfg(feature = "doc")]
     Doc(Doc),
+    /// Show which definitions a class is made from and the file and line of each
+    Explain(Explain),
+    /// Print the css that classes are converted to
+    Convert(Convert),
+    /// Make the css once and exit with an error if any file could not be parsed
+    Build(Build),
 }
 
 #[derive(Parser)]
@@ -51,6 +176,88 @@ pub struct Doc {
     pub output: PathBuf,
 }
 
+#[derive(Parser)]
+pub struct Explain {
+    /// The classes to explain
+    #[clap(required = true)]
+    pub classes: Vec<String>,
+
+    #[command(flatten)]
+    pub definitions: Definitions,
+}
+
+#[derive(Parser)]
+pub struct Convert {
+    /// The classes to convert
+    #[clap(required = true)]
+    pub classes: Vec<String>,
+
+    /// Show every class that was looked up and the definition it used before the css
+    #[clap(short, long, value_parser, default_value_t = false)]
+    pub trace: bool,
+
+    /// Write the css with nothing that is not needed, this is the default
+    #[clap(long, value_parser, default_value_t = false, overrides_with = "pretty")]
+    pub minify: bool,
+
+    /// Write the css with each rule and declaration on its own line
+    #[clap(long, value_parser, default_value_t = false, overrides_with = "minify")]
+    pub pretty: bool,
+
+    #[command(flatten)]
+    pub definitions: Definitions,
+}
+
+#[derive(Parser)]
+pub struct Build {
+    #[command(flatten)]
+    pub options: Options,
+
+    /// Also exit with an error if any class did not match a definition
+    #[clap(long, value_parser, default_value_t = false)]
+    pub strict: bool,
+}
+
+/// Where to load the definitions from for the commands that convert classes once
+#[derive(clap::Args)]
+pub struct Definitions {
+    /// This is the path to where your html and javascript is
+    #[clap(short, long, value_parser)]
+    pub source_code: Option<PathBuf>,
+
+    /// The path to where your custom neum files are defined, defaults to the same as your source code location
+    #[clap(short, long, value_parser)]
+    pub neum_folder: Option<PathBuf>,
+
+    /// Path to folders or files of neum files to exclude
+    #[clap(short, long, value_parser)]
+    pub exclude: Vec<PathBuf>,
+
+    /// Use the most specific definition for a class instead of the one with the highest priority
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "no_specific"
+    )]
+    pub specific: bool,
+
+    /// Use the definition with the highest priority even if neum.toml sets `specific`
+    #[clap(
+        long,
+        value_parser,
+        default_value_t = false,
+        overrides_with = "specific"
+    )]
+    pub no_specific: bool,
+}
+
 lazy_static! {
     pub static ref ARGS: Args = Args::parse();
+    /// The options of the build command if it is used or else the ones for watching, along with
+    /// the ones from the config file
+    pub static ref OPTIONS: Options = config::options(match &ARGS.command {
+        Some(Commands::Build(x)) => &x.options,
+        _ => &ARGS.options,
+    });
 }
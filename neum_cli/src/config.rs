@@ -0,0 +1,417 @@
+use crate::args::{Definitions, Options};
+use anyhow::Context;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    static ref FOUND: Found = env::current_dir()
+        .map_err(anyhow::Error::from)
+        .and_then(|x| find(&x))
+        .unwrap_or_else(|e| {
+            eprintln!("{e:#}");
+            std::process::exit(1);
+        });
+    pub static ref CONFIG: &'static Config = &FOUND.config;
+    static ref FILES: Files = Files::new(&CONFIG).unwrap_or_else(|e| {
+        eprintln!("{e:#}");
+        std::process::exit(1);
+    });
+}
+
+/// The settings in a project's `neum.toml`, paths in it are from the folder it is in
+/// ```toml
+/// source_code = "src"
+/// output = ["public/neum.css", "docs/neum.css"]
+/// exclude_globs = ["**/*.test.tsx"]
+/// safelist = ["bg-{red,teal}", "/text-.*/"]
+/// blocklist = ["float-left"]
+/// pretty = true
+///
+/// [defaults]
+/// skip = ["cursor", "shadow"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub source_code: Option<PathBuf>,
+    pub neum_folder: Option<PathBuf>,
+    /// Path to folders or files to exclude
+    pub exclude: Vec<PathBuf>,
+    /// Only the source files that match one of these are read, if there are any
+    pub include_globs: Vec<String>,
+    /// Files that match any of these are not read
+    pub exclude_globs: Vec<String>,
+    /// One or more css files, each one gets all of the css
+    pub output: Outputs,
+    pub watch: Option<bool>,
+    pub verbose: bool,
+    pub specific: bool,
+    pub ambiguous: bool,
+    pub report: bool,
+    pub report_json: Option<PathBuf>,
+    pub cache_size: Option<usize>,
+    /// Classes that are always made even if they are not used
+    pub safelist: Vec<String>,
+    /// Classes that are never made even if they are used
+    pub blocklist: Vec<String>,
+    /// Write the css with each rule and declaration on its own line instead of minifying it
+    pub pretty: bool,
+    /// Write a `.map` next to each output with the file and line of the definition each
+    /// declaration came from
+    pub source_map: bool,
+    pub defaults: Defaults,
+    /// Extractors for files that the built in ones do not handle or should handle differently
+    pub extractors: Vec<ExtractorConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(untagged)]
+pub enum Outputs {
+    #[default]
+    None,
+    One(PathBuf),
+    Many(Vec<PathBuf>),
+}
+
+/// Which of the default definitions are loaded
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    /// If false none of the defaults are loaded
+    pub load: bool,
+    /// The default libraries to not load, named by their file like `colors` or `margin`
+    pub skip: Vec<String>,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Defaults {
+            load: true,
+            skip: Vec::new(),
+        }
+    }
+}
+
+/// Finds classes in the files that match any of the globs
+/// ```toml
+/// [[extractors]]
+/// globs = ["templates/**/*.jinja"]
+/// regex = 'class="([^"]*)"'
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractorConfig {
+    pub globs: Vec<String>,
+    /// Without a regex anything that looks like a class is found
+    pub regex: Option<String>,
+}
+
+impl Config {
+    /// Reads a config file, giving the default settings if there is not one
+    pub fn load(path: &Path) -> anyhow::Result<Config> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+
+    /// If a default library is loaded, `file` being where it was defined
+    pub fn loads_default(&self, file: Option<&str>) -> bool {
+        self.defaults.load
+            && !file
+                .and_then(|x| Path::new(x).file_stem())
+                .is_some_and(|x| self.defaults.skip.iter().any(|y| x == y.as_str()))
+    }
+}
+
+/// The closest `neum.toml` to where neum was run
+struct Found {
+    /// The folder it is in
+    root: Option<PathBuf>,
+    /// Where neum was run from the root
+    subfolder: PathBuf,
+    config: Config,
+}
+
+/// Looks for a `neum.toml` in the `current` folder and then each one above it
+fn find(current: &Path) -> anyhow::Result<Found> {
+    for i in current.ancestors() {
+        let path = i.join("neum.toml");
+        if path.is_file() {
+            return Ok(Found {
+                root: Some(i.to_path_buf()),
+                subfolder: current.strip_prefix(i)?.to_path_buf(),
+                config: Config::load(&path)?,
+            });
+        }
+    }
+    Ok(Found {
+        root: None,
+        subfolder: PathBuf::new(),
+        config: Config::default(),
+    })
+}
+
+/// Moves into the folder of the config file so that the paths in it work from any folder in the
+/// project, this has to be done before any paths are used
+pub fn enter() {
+    if let Some(root) = &FOUND.root {
+        if let Err(e) = env::set_current_dir(root) {
+            eprintln!("Failed to move to {}: {e}", root.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// A setting that is turned on with `on` or off with `off` on the command line, or else by the
+/// config file
+pub fn flag(on: bool, off: bool, config: bool) -> bool {
+    on || (config && !off)
+}
+
+/// The options given on the command line with the ones from the config file used for anything
+/// that was not given
+pub fn options(cli: &Options) -> Options {
+    merge(cli, &CONFIG, &FOUND.subfolder)
+}
+
+/// The same as [`options`] for the commands that only load definitions
+pub fn definitions(cli: &Definitions) -> Definitions {
+    merge_definitions(cli, &CONFIG, &FOUND.subfolder)
+}
+
+/// Merges the options with a config file, `subfolder` being where neum was run from its folder
+/// since the paths given on the command line are from there
+fn merge(cli: &Options, config: &Config, subfolder: &Path) -> Options {
+    let rebase = |path: &Path| subfolder.join(path);
+    Options {
+        source_code: cli
+            .source_code
+            .as_deref()
+            .map(rebase)
+            .or(config.source_code.clone()),
+        neum_folder: cli
+            .neum_folder
+            .as_deref()
+            .map(rebase)
+            .or(config.neum_folder.clone()),
+        exclude: cli
+            .exclude
+            .iter()
+            .map(|x| rebase(x))
+            .chain(config.exclude.iter().cloned())
+            .collect(),
+        output: match (cli.output.is_empty(), &config.output) {
+            (false, _) => cli.output.iter().map(|x| rebase(x)).collect(),
+            (true, Outputs::None) => Vec::new(),
+            (true, Outputs::One(x)) => vec![x.clone()],
+            (true, Outputs::Many(x)) => x.clone(),
+        },
+        verbose: flag(cli.verbose, cli.no_verbose, config.verbose),
+        no_verbose: cli.no_verbose,
+        specific: flag(cli.specific, cli.no_specific, config.specific),
+        no_specific: cli.no_specific,
+        ambiguous: flag(cli.ambiguous, cli.no_ambiguous, config.ambiguous),
+        no_ambiguous: cli.no_ambiguous,
+        report: flag(cli.report, cli.no_report, config.report),
+        no_report: cli.no_report,
+        report_json: cli
+            .report_json
+            .as_deref()
+            .map(rebase)
+            .or(config.report_json.clone()),
+        cache_size: cli.cache_size.or(config.cache_size),
+        safelist: cli
+            .safelist
+            .iter()
+            .chain(&config.safelist)
+            .cloned()
+            .collect(),
+        blocklist: cli
+            .blocklist
+            .iter()
+            .chain(&config.blocklist)
+            .cloned()
+            .collect(),
+        minify: cli.minify,
+        pretty: flag(cli.pretty, cli.minify, config.pretty),
+        source_map: flag(cli.source_map, cli.no_source_map, config.source_map),
+        no_source_map: cli.no_source_map,
+    }
+}
+
+fn merge_definitions(cli: &Definitions, config: &Config, subfolder: &Path) -> Definitions {
+    let rebase = |path: &Path| subfolder.join(path);
+    Definitions {
+        source_code: cli
+            .source_code
+            .as_deref()
+            .map(rebase)
+            .or(config.source_code.clone()),
+        neum_folder: cli
+            .neum_folder
+            .as_deref()
+            .map(rebase)
+            .or(config.neum_folder.clone()),
+        exclude: cli
+            .exclude
+            .iter()
+            .map(|x| rebase(x))
+            .chain(config.exclude.iter().cloned())
+            .collect(),
+        specific: flag(cli.specific, cli.no_specific, config.specific),
+        no_specific: cli.no_specific,
+    }
+}
+
+/// If a file should not be read, either being in one of the `exclude` paths or matching the
+/// globs of the config file, `source` being if it is a source file that classes are found in
+pub fn excluded(path: &Path, exclude: &[PathBuf], source: bool) -> bool {
+    let path = path.strip_prefix(".").unwrap_or(path);
+    exclude
+        .iter()
+        .any(|x| path.starts_with(x.strip_prefix(".").unwrap_or(x)))
+        || FILES.exclude.is_match(path)
+        || (source && FILES.include.as_ref().is_some_and(|x| !x.is_match(path)))
+}
+
+struct Files {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl Files {
+    fn new(config: &Config) -> anyhow::Result<Files> {
+        Ok(Files {
+            include: match config.include_globs.is_empty() {
+                true => None,
+                false => Some(globs(&config.include_globs)?),
+            },
+            exclude: globs(&config.exclude_globs)?,
+        })
+    }
+}
+
+fn globs(globs: &[String]) -> anyhow::Result<GlobSet> {
+    let mut set = GlobSetBuilder::new();
+    for i in globs {
+        set.add(Glob::new(i).with_context(|| format!("Invalid glob `{i}`"))?);
+    }
+    Ok(set.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::Args;
+    use clap::Parser;
+
+    /// The options from some command line arguments merged with a config file, as if neum was run
+    /// from `sub` in the project
+    fn merged(args: &[&str], config: &str) -> Options {
+        let args = Args::try_parse_from(["neum"].iter().chain(args)).unwrap();
+        let config = toml::from_str(config).unwrap();
+        merge(&args.options, &config, Path::new("sub"))
+    }
+
+    /// A new empty folder in the temp folder
+    fn folder(name: &str) -> PathBuf {
+        let folder = env::temp_dir().join(format!("neum-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn config_used_when_not_given() {
+        let options = merged(&[], "verbose = true\nreport = true\nsource_code = \"src\"");
+        assert!(options.verbose);
+        assert!(options.report);
+        assert!(!options.ambiguous);
+        assert_eq!(options.source_code, Some(PathBuf::from("src")));
+    }
+
+    #[test]
+    fn cli_turns_off_config() {
+        let config = "verbose = true\nspecific = true\nambiguous = true\nreport = true\npretty = true\nsource_map = true";
+        let options = merged(
+            &[
+                "--no-verbose",
+                "--no-specific",
+                "--no-ambiguous",
+                "--no-report",
+                "--minify",
+                "--no-source-map",
+            ],
+            config,
+        );
+        assert!(!options.verbose);
+        assert!(!options.specific);
+        assert!(!options.ambiguous);
+        assert!(!options.report);
+        assert!(!options.pretty);
+        assert!(!options.source_map);
+    }
+
+    #[test]
+    fn last_flag_wins() {
+        assert!(merged(&["--no-verbose", "--verbose"], "verbose = true").verbose);
+        assert!(!merged(&["--verbose", "--no-verbose"], "").verbose);
+        assert!(merged(&["--minify", "--pretty"], "").pretty);
+        assert!(!merged(&["--pretty", "--minify"], "pretty = true").pretty);
+    }
+
+    #[test]
+    fn cli_paths_from_subfolder() {
+        let options = merged(
+            &["-s", "app", "-o", "a.css", "-o", "b.css", "-e", "old"],
+            "output = \"neum.css\"\nsource_code = \"src\"\nexclude = [\"dist\"]",
+        );
+        assert_eq!(options.source_code, Some(PathBuf::from("sub/app")));
+        assert_eq!(
+            options.output,
+            vec![PathBuf::from("sub/a.css"), PathBuf::from("sub/b.css")]
+        );
+        assert_eq!(
+            options.exclude,
+            vec![PathBuf::from("sub/old"), PathBuf::from("dist")]
+        );
+        assert_eq!(
+            merged(&[], "output = [\"a.css\", \"b.css\"]").output.len(),
+            2
+        );
+    }
+
+    #[test]
+    fn find_walks_up() {
+        let root = folder("find");
+        let nested = root.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join("neum.toml"), "pretty = true").unwrap();
+        let found = find(&nested).unwrap();
+        assert_eq!(found.root, Some(root.clone()));
+        assert_eq!(found.subfolder, Path::new("a").join("b"));
+        assert!(found.config.pretty);
+
+        fs::write(root.join("a").join("neum.toml"), "").unwrap();
+        let found = find(&nested).unwrap();
+        assert_eq!(found.root, Some(root.join("a")));
+        assert_eq!(found.subfolder, PathBuf::from("b"));
+        assert!(!found.config.pretty);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn find_invalid_config() {
+        let root = folder("invalid");
+        fs::write(root.join("neum.toml"), "unknown = 1").unwrap();
+        assert!(find(&root).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+use crate::config::CONFIG;
+use anyhow::Context;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use html_parser::{Dom, Node};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::Path;
+
+lazy_static! {
+    /// Where a list of classes starts in a script or a template
+    static ref CLASSES: Regex = Regex::new(
+        r#"(?:\bclassName|\bclass:list|(?:\bv-bind)?:class|\bclass)\s*=\s*["'{]|\b(?:clsx|classnames|classNames)\s*\(|\bclass:([^\s=>/{}"']+)"#
+    )
+    .unwrap();
+    /// The built in extractors followed by the ones from the config file
+    pub static ref EXTRACTORS: Extractors = configured().unwrap_or_else(|e| {
+        eprintln!("{e:#}");
+        std::process::exit(1);
+    });
+}
+
+fn configured() -> anyhow::Result<Extractors> {
+    let mut extractors = Extractors::builtin();
+    for i in &CONFIG.extractors {
+        let pattern = match &i.regex {
+            Some(regex) => Pattern::new(regex)
+                .with_context(|| format!("Invalid regex for {}", i.globs.join(", ")))?,
+            None => Pattern::tokens(),
+        };
+        extractors
+            .register(&i.globs, pattern)
+            .with_context(|| format!("Invalid glob in {}", i.globs.join(", ")))?;
+    }
+    Ok(extractors)
+}
+
+/// Finds the classes used in a file
+pub trait Extractor: Send + Sync {
+    fn extract(&self, content: &str) -> anyhow::Result<Vec<String>>;
+}
+
+/// Extractors along with the globs of the files they are used for, the last one registered that
+/// matches a file is used
+#[derive(Default)]
+pub struct Extractors {
+    extractors: Vec<(GlobSet, Box<dyn Extractor>)>,
+}
+
+impl Extractors {
+    /// Html files along with JSX, TSX, Vue, Svelte and JavaScript
+    pub fn builtin() -> Extractors {
+        let mut extractors = Extractors::default();
+        extractors
+            .register(&["*.html", "*.htm", "*.xhtml"], Html)
+            .unwrap();
+        extractors
+            .register(
+                &[
+                    "*.jsx", "*.tsx", "*.js", "*.ts", "*.mjs", "*.cjs", "*.vue", "*.svelte",
+                ],
+                Script,
+            )
+            .unwrap();
+        extractors
+    }
+
+    pub fn register<S: AsRef<str>>(
+        &mut self,
+        globs: &[S],
+        extractor: impl Extractor + 'static,
+    ) -> Result<(), globset::Error> {
+        let mut set = GlobSetBuilder::new();
+        for i in globs {
+            set.add(Glob::new(i.as_ref())?);
+        }
+        self.extractors.push((set.build()?, Box::new(extractor)));
+        Ok(())
+    }
+
+    /// The extractor used for a file, paths are matched without a leading `./`
+    pub fn find(&self, path: &Path) -> Option<&dyn Extractor> {
+        let path = path.strip_prefix(".").unwrap_or(path);
+        self.extractors
+            .iter()
+            .rev()
+            .find(|x| x.0.is_match(path))
+            .map(|x| x.1.as_ref())
+    }
+
+    /// If classes can be found in a file
+    pub fn supported(&self, path: &Path) -> bool {
+        self.find(path).is_some()
+    }
+
+    /// Finds every class used in a file, nothing is found if no extractor is used for it
+    pub fn classes(&self, path: &Path, content: &str) -> anyhow::Result<Vec<String>> {
+        match self.find(path) {
+            Some(extractor) => extractor.extract(content),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// The classes of every element in a html file
+pub struct Html;
+
+impl Extractor for Html {
+    fn extract(&self, content: &str) -> anyhow::Result<Vec<String>> {
+        Ok(Dom::parse(content)?
+            .children
+            .iter()
+            .flat_map(elements)
+            .collect())
+    }
+}
+
+fn elements(node: &Node) -> Vec<String> {
+    if let Some(element) = node.element() {
+        let mut classes = element.classes.clone();
+        for i in &element.children {
+            classes.append(&mut elements(i));
+        }
+        return classes;
+    }
+    Vec::new()
+}
+
+/// The classes in `class` and `className` attributes, Svelte `class:name` directives, Vue `:class`
+/// bindings and the strings given to `class:list`, `clsx` and `classnames`, anything worked out
+/// while running is missed
+pub struct Script;
+
+impl Extractor for Script {
+    fn extract(&self, content: &str) -> anyhow::Result<Vec<String>> {
+        Ok(scripts(content))
+    }
+}
+
+/// Every match of a regex, or what its first group captured if it has one, split at whitespace
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(regex: &str) -> Result<Pattern, regex::Error> {
+        Ok(Pattern(Regex::new(regex)?))
+    }
+
+    /// Anything that looks like a class, for files where classes can be anywhere, this finds a lot
+    /// of words that are not classes as well
+    pub fn tokens() -> Pattern {
+        Pattern(Regex::new(r"[\w:%./\[\]#()-]*[\w%\])]").unwrap())
+    }
+}
+
+impl Extractor for Pattern {
+    fn extract(&self, content: &str) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .0
+            .captures_iter(content)
+            .filter_map(|x| x.get(1).or_else(|| x.get(0)))
+            .flat_map(|x| {
+                x.as_str()
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
+}
+
+fn scripts(content: &str) -> Vec<String> {
+    let mut classes = Vec::new();
+    for i in CLASSES.captures_iter(content) {
+        let found = i.get(0).unwrap();
+        if let Some(name) = i.get(1) {
+            if name.as_str() != "list" {
+                classes.push(name.as_str().to_string());
+            }
+            continue;
+        }
+        let rest = &content[found.end()..];
+        let value = match found.as_str().chars().last() {
+            Some('(') => expression(enclosed(rest, '(', ')')),
+            Some('{') => expression(enclosed(rest, '{', '}')),
+            Some(quote) => {
+                let value = quoted(rest, quote);
+                if found.as_str().starts_with(':') || found.as_str().starts_with('v') {
+                    expression(value)
+                } else {
+                    attribute(value)
+                }
+            }
+            None => Vec::new(),
+        };
+        classes.extend(value);
+    }
+    classes
+}
+
+/// The words in an attribute, along with the strings in any `{ }` in it like Svelte allows
+fn attribute(value: &str) -> Vec<String> {
+    let mut classes = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('{') {
+        classes.extend(rest[..start].split_whitespace().map(String::from));
+        let inner = enclosed(&rest[start + 1..], '{', '}');
+        classes.extend(expression(inner));
+        rest = rest[start + 1 + inner.len()..]
+            .strip_prefix('}')
+            .unwrap_or_default();
+    }
+    classes.extend(rest.split_whitespace().map(String::from));
+    classes
+}
+
+/// Functions whose arguments are classes, objects given to any other function are skipped
+const HELPERS: &[&str] = &["clsx", "classnames", "classNames"];
+
+/// The words in every string and the keys of objects that are not in another object or given to
+/// a function other than `clsx` and `classnames`, words with quotes in them are skipped as they
+/// are never classes
+fn expression(expression: &str) -> Vec<String> {
+    strings(expression)
+        .iter()
+        .flat_map(|x| x.split_whitespace())
+        .filter(|x| !x.contains(['"', '\'', '`']))
+        .map(String::from)
+        .collect()
+}
+
+/// Every string in an expression along with the keys of the objects classes are in, template
+/// literals are split at each `${ }` with the strings inside of it found as well
+fn strings(expression: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    // if objects in each bracket that is open have classes as keys
+    let mut open = Vec::new();
+    let mut rest = expression;
+    while let Some(start) = rest.find(['"', '\'', '`', '{', '(', '[', ')', ']']) {
+        let x = rest[start..].chars().next().unwrap();
+        let before = &rest[..start];
+        rest = &rest[start + 1..];
+        match x {
+            '"' | '\'' | '`' => {
+                let value = quoted(rest, x);
+                strings.append(&mut string(value, x));
+                rest = rest[value.len()..].strip_prefix(x).unwrap_or_default();
+            }
+            '{' => {
+                let inner = enclosed(rest, '{', '}');
+                if open.iter().all(|x| *x) {
+                    strings.extend(entries(inner).into_iter().filter_map(key));
+                }
+                rest = rest[inner.len()..].strip_prefix('}').unwrap_or_default();
+            }
+            '(' => {
+                let callee = before
+                    .trim_end()
+                    .rsplit(|x: char| !x.is_alphanumeric() && x != '_' && x != '$')
+                    .next()
+                    .unwrap_or_default();
+                open.push(callee.is_empty() || HELPERS.contains(&callee));
+            }
+            '[' => open.push(true),
+            _ => {
+                open.pop();
+            }
+        }
+    }
+    strings
+}
+
+/// The text of a string without escapes, a template literal is split at each `${ }`
+fn string(value: &str, quote: char) -> Vec<String> {
+    if quote != '`' {
+        return vec![unescape(value)];
+    }
+    let mut strings = Vec::new();
+    let mut template = value;
+    while let Some(start) = template.find("${") {
+        strings.push(unescape(&template[..start]));
+        let inner = enclosed(&template[start + 2..], '{', '}');
+        strings.append(&mut self::strings(inner));
+        template = template[start + 2 + inner.len()..]
+            .strip_prefix('}')
+            .unwrap_or_default();
+    }
+    strings.push(unescape(template));
+    strings
+}
+
+/// The text of a string with each `\` removed, escaped whitespace is made into a space
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(x) = chars.next() {
+        match x {
+            '\\' => match chars.next() {
+                Some('n' | 't' | 'r') => unescaped.push(' '),
+                Some(x) => unescaped.push(x),
+                None => {}
+            },
+            x => unescaped.push(x),
+        }
+    }
+    unescaped
+}
+
+/// The `key: value` pairs of an object, split at the commas that are not in brackets or strings
+fn entries(object: &str) -> Vec<&str> {
+    let mut entries = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut chars = object.char_indices();
+    while let Some((i, x)) = chars.next() {
+        match x {
+            '"' | '\'' | '`' => {
+                let value = quoted(&object[i + 1..], x);
+                for _ in 0..value.chars().count() + 1 {
+                    chars.next();
+                }
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push(&object[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    entries.push(&object[start..]);
+    entries
+}
+
+/// The key of a `key: value` pair or of a shorthand like `{ active }`, computed keys and spreads
+/// are skipped
+fn key(entry: &str) -> Option<String> {
+    let entry = entry.trim();
+    if let Some(quote) = entry.chars().next().filter(|x| matches!(x, '"' | '\'')) {
+        return Some(unescape(quoted(&entry[1..], quote)));
+    }
+    let key = entry.split(':').next().unwrap_or_default().trim();
+    (key.starts_with(|x: char| x.is_alphabetic() || x == '_')
+        && key
+            .chars()
+            .all(|x| x.is_alphanumeric() || x == '_' || x == '-'))
+    .then(|| key.to_string())
+}
+
+/// The text up to the closing quote, skipping escaped quotes and the `${ }` of template literals
+fn quoted(text: &str, quote: char) -> &str {
+    let mut chars = text.char_indices();
+    while let Some((i, x)) = chars.next() {
+        match x {
+            '\\' => {
+                chars.next();
+            }
+            '$' if quote == '`' && text[i + 1..].starts_with('{') => {
+                let inner = enclosed(&text[i + 2..], '{', '}');
+                // skip past the `{`, what is inside and the `}`
+                for _ in 0..inner.chars().count() + 2 {
+                    chars.next();
+                }
+            }
+            x if x == quote => return &text[..i],
+            _ => {}
+        }
+    }
+    text
+}
+
+/// The text up to the bracket that closes one that was just opened, skipping the brackets in
+/// strings
+fn enclosed(text: &str, open: char, close: char) -> &str {
+    let mut depth = 0;
+    let mut chars = text.char_indices();
+    while let Some((i, x)) = chars.next() {
+        match x {
+            '"' | '\'' | '`' => {
+                let value = quoted(&text[i + 1..], x);
+                for _ in 0..value.chars().count() + 1 {
+                    chars.next();
+                }
+            }
+            x if x == open => depth += 1,
+            x if x == close && depth == 0 => return &text[..i],
+            x if x == close => depth -= 1,
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The classes found in some content, sorted and without repeats the same as they are used
+    fn classes(content: &str) -> Vec<String> {
+        let mut classes = scripts(content);
+        classes.sort();
+        classes.dedup();
+        classes
+    }
+
+    #[test]
+    fn class_name() {
+        assert_eq!(
+            classes(r#"<div className="p-4 bg-red" class='m-2'>"#),
+            vec!["bg-red", "m-2", "p-4"]
+        );
+    }
+
+    #[test]
+    fn class_name_expression() {
+        assert_eq!(
+            classes(r#"<a className={"w-5 " + (big ? "h-10" : 'h-5')}>"#),
+            vec!["h-10", "h-5", "w-5"]
+        );
+    }
+
+    #[test]
+    fn clsx_object() {
+        assert_eq!(
+            classes(
+                r#"<a className={clsx("p-2", { "bg-red": error, active: isActive, nested: { x: 1 } })}>"#
+            ),
+            vec!["active", "bg-red", "nested", "p-2"]
+        );
+        assert_eq!(
+            classes(r#"classnames('m-1', cond && "m-2")"#),
+            vec!["m-1", "m-2"]
+        );
+    }
+
+    #[test]
+    fn template_literal() {
+        assert_eq!(
+            classes(r#"<a className={`p-4 ${active ? "bg-red" : `bg-${"blue"}`} m-1`}>"#),
+            vec!["bg-", "bg-red", "blue", "m-1", "p-4"]
+        );
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        assert_eq!(
+            classes(r#"<a className={"w-5 \"quoted\" h-5"} class="m-1">"#),
+            vec!["h-5", "m-1", "w-5"]
+        );
+    }
+
+    #[test]
+    fn nested_braces() {
+        assert_eq!(
+            classes(r#"<a className={clsx({ "p-1": a }, fn({ b: "}" }), "m-2")} class="h-1">"#),
+            vec!["h-1", "m-2", "p-1"]
+        );
+    }
+
+    #[test]
+    fn vue_class() {
+        assert_eq!(
+            classes(r#"<div :class="{ 'bg-red': error, active: isActive }" class="p-2">"#),
+            vec!["active", "bg-red", "p-2"]
+        );
+        assert_eq!(
+            classes(r#"<div v-bind:class="['m-1', big ? 'h-10' : 'h-5']">"#),
+            vec!["h-10", "h-5", "m-1"]
+        );
+        assert_eq!(
+            classes(r#"<div :class="[{ 'p-1': a, b: { c: 'd' } }, fn({ e: f })]">"#),
+            vec!["b", "p-1"]
+        );
+    }
+
+    #[test]
+    fn svelte_class() {
+        assert_eq!(
+            classes(r#"<div class:bg-red={error} class:active class="p-2 {big ? 'h-10' : ''}">"#),
+            vec!["active", "bg-red", "h-10", "p-2"]
+        );
+    }
+}
@@ -1,5 +1,5 @@
-use crate::ARGS;
-use html_parser::{Dom, Node};
+use crate::extract::EXTRACTORS;
+use crate::OPTIONS;
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -18,27 +18,18 @@ pub fn update_html(path: PathBuf) -> anyhow::Result<()> {
     print!(
         "Updating: {}{}",
         path.display(),
-        match ARGS.verbose {
+        match OPTIONS.verbose {
             true => "",
             false => "\n",
         }
     );
     io::stdout().flush().unwrap();
     let now = Instant::now();
-    let mut real_classes = Vec::new();
     if let Ok(content) = fs::read_to_string(path.clone()) {
-        let classes = Dom::parse(&content)?
-            .children
-            .iter()
-            .map(|x| get_classes(x.clone()))
-            .collect::<Vec<Vec<String>>>();
-        for i in &classes {
-            real_classes.append(&mut i.clone());
-        }
-        real_classes = real_classes
-            .iter()
+        let real_classes = EXTRACTORS
+            .classes(&path, &content)?
+            .into_iter()
             .unique()
-            .cloned()
             .collect::<Vec<String>>();
         let html_files = HTML_FILES.clone();
         let mut html_files = html_files.lock().unwrap();
@@ -52,19 +43,8 @@ pub fn update_html(path: PathBuf) -> anyhow::Result<()> {
         let mut html_files = html_files.lock().unwrap();
         html_files.remove(&path);
     }
-    if ARGS.verbose {
+    if OPTIONS.verbose {
         println!(" in {:?}", now.elapsed());
     }
     Ok(())
 }
-
-fn get_classes(node: Node) -> Vec<String> {
-    if let Some(element) = node.element() {
-        let mut classes = element.classes.clone();
-        for i in &element.children {
-            classes.append(&mut get_classes(i.clone()));
-        }
-        return classes;
-    }
-    Vec::new()
-}
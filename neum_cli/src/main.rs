@@ -1,18 +1,40 @@
 mod args;
-use args::ARGS;
+use args::{ARGS, OPTIONS};
+mod config;
+mod extract;
 mod html_parse;
 mod neum_parse;
+mod oneshot;
 mod output;
+mod report;
+mod sourcemap;
 mod watcher;
 
 #[cfg(feature = "doc")]
 mod doc;
 
 fn main() {
+    #[cfg(feature = "doc")]
+    let docs = matches!(ARGS.command, Some(args::Commands::Doc(_)));
+    #[cfg(not(feature = "doc"))]
+    let docs = false;
+    if !docs {
+        config::enter();
+    }
+    if matches!(ARGS.command, None | Some(args::Commands::Build(_))) && OPTIONS.output.is_empty() {
+        eprintln!("No output file was given, use --output or set `output` in neum.toml");
+        std::process::exit(2);
+    }
+
     match &ARGS.command {
         None => {
             watcher::init();
-            if ARGS.watch {
+            output::update(true);
+            if config::flag(
+                ARGS.watching,
+                !ARGS.watch,
+                config::CONFIG.watch.unwrap_or(true),
+            ) {
                 watcher::watch();
             }
         }
@@ -20,5 +42,8 @@ fn main() {
         Some(args::Commands::Doc(_)) => {
             doc::walk::walk_neum_folder();
         }
+        Some(args::Commands::Explain(args)) => oneshot::explain(args),
+        Some(args::Commands::Convert(args)) => oneshot::convert(args),
+        Some(args::Commands::Build(args)) => oneshot::build(args),
     }
 }
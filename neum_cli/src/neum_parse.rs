@@ -1,44 +1,69 @@
-use crate::ARGS;
+use crate::{config, OPTIONS};
 use lazy_static::lazy_static;
 use neum::Neum;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use walkdir::WalkDir;
 
 lazy_static! {
     pub static ref NEUM_FILES: Arc<Mutex<HashMap<PathBuf, Neum>>> =
         Arc::new(Mutex::new(HashMap::new()));
 }
 
+/// Every neum file that is not excluded, they are found in the neum folder if there is one or else
+/// in the source code
+pub fn neum_paths(
+    source_code: Option<&Path>,
+    neum_folder: Option<&Path>,
+    exclude: &[PathBuf],
+) -> Vec<PathBuf> {
+    WalkDir::new(
+        neum_folder
+            .or(source_code)
+            .unwrap_or_else(|| Path::new(".")),
+    )
+    .into_iter()
+    .flatten()
+    .map(|x| x.into_path())
+    .filter(|x| x.extension().is_some_and(|x| x == "neum"))
+    .filter(|x| !config::excluded(x, exclude, false))
+    .collect()
+}
+
+/// Parses a neum file, giving `None` if it can not be read
+pub fn read_neum(path: &Path) -> Result<Option<Neum>, neum::error::NeumError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(Neum::new(content, Some(path.display().to_string()))?)),
+        Err(_) => Ok(None),
+    }
+}
+
 pub fn update_neum(path: PathBuf) -> Result<(), neum::error::NeumError> {
     print!(
         "Updating: {}{}",
         path.display(),
-        match ARGS.verbose {
+        match OPTIONS.verbose {
             true => "",
             false => "\n",
         }
     );
     io::stdout().flush().unwrap();
     let now = Instant::now();
-    if let Ok(content) = &fs::read_to_string(path.clone()) {
-        let neum = Neum::new(content.clone(), Some(path.display().to_string()))?;
-        let neum_files = NEUM_FILES.clone();
-        let mut neum_files = neum_files.lock().unwrap();
-        if let Some(i) = neum_files.get_mut(&path) {
-            *i = neum;
-        } else {
+    let neum_files = NEUM_FILES.clone();
+    let mut neum_files = neum_files.lock().unwrap();
+    match read_neum(&path)? {
+        Some(neum) => {
             neum_files.insert(path, neum);
         }
-    } else {
-        let neum_files = NEUM_FILES.clone();
-        let mut neum_files = neum_files.lock().unwrap();
-        neum_files.remove(&path);
+        None => {
+            neum_files.remove(&path);
+        }
     }
-    if ARGS.verbose {
+    if OPTIONS.verbose {
         println!(" in {:?}", now.elapsed());
     }
     Ok(())
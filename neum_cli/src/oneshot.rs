@@ -0,0 +1,95 @@
+use crate::args::{Build, Convert, Definitions, Explain};
+use crate::config::{self, CONFIG};
+use crate::{neum_parse, output, report, watcher, OPTIONS};
+use neum::{css, Neum};
+use std::collections::HashMap;
+use std::process;
+use std::time::Instant;
+
+/// Loads the definitions the same way as watching does, without keeping them for later
+pub fn load(args: &Definitions) -> Neum {
+    let args = config::definitions(args);
+    let mut neum_files = HashMap::new();
+    for path in neum_parse::neum_paths(
+        args.source_code.as_deref(),
+        args.neum_folder.as_deref(),
+        &args.exclude,
+    ) {
+        match neum_parse::read_neum(&path) {
+            Ok(Some(neum)) => {
+                neum_files.insert(path, neum);
+            }
+            Ok(None) => eprintln!("Failed to read {}", path.display()),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+
+    let mut neum = output::definitions(&mut neum_files);
+    if args.specific {
+        neum.set_matching(neum::Matching::Specific);
+    }
+    neum
+}
+
+pub fn explain(args: &Explain) {
+    let neum = load(&args.definitions);
+    for i in &args.classes {
+        print!("{}", neum.explain(i));
+    }
+}
+
+/// Prints the css of each class, exiting with an error if any of them could not be converted
+pub fn convert(args: &Convert) {
+    let neum = load(&args.definitions);
+    let format = output::format(config::flag(args.pretty, args.minify, CONFIG.pretty));
+    let mut failed = false;
+    for i in &args.classes {
+        if args.trace {
+            print!("{}", neum.explain(i));
+        }
+        match neum.style(i) {
+            Ok(Some(x)) => println!("{}", css::render(&[x], format).trim_end()),
+            Ok(None) => {
+                eprintln!("`{i}` did not match any definition");
+                failed = true;
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Makes the css once, exiting with an error if any file could not be parsed or, when strict, if
+/// any class was not turned into css
+pub fn build(args: &Build) {
+    let now = Instant::now();
+    let failed = watcher::init();
+    let summary = output::update(true);
+    if args.strict && !OPTIONS.report {
+        report::print(&summary.unresolved);
+    }
+    println!(
+        "Built {} from {} neum files and {} source files: {} classes, {} rules, {} unresolved, {} files failed in {:?}",
+        OPTIONS
+            .output
+            .iter()
+            .map(|x| x.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        summary.neum_files,
+        summary.html_files,
+        summary.classes,
+        summary.rules,
+        summary.unresolved.len(),
+        failed,
+        now.elapsed()
+    );
+    if failed > 0 || (args.strict && !summary.unresolved.is_empty()) {
+        process::exit(1);
+    }
+}
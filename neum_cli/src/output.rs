@@ -1,18 +1,132 @@
-use crate::{html_parse, neum_parse, ARGS};
+use crate::config::CONFIG;
+use crate::report::{self, Unresolved};
+use crate::sourcemap::SourceMap;
+use crate::{html_parse, neum_parse, OPTIONS};
 use itertools::Itertools;
+use neum::cache::CachePolicy;
+use neum::css::{self, Block, Format};
+use neum::list::ClassList;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Component;
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 lazy_static::lazy_static! {
-    static ref REAL_DEFAULTS: Arc<neum::Neum> = Arc::new(neum::Neum::default());
+    static ref REAL_DEFAULTS: Arc<neum::Neum> = {
+        let mut defaults = neum::Neum::default();
+        defaults.retain(|x| CONFIG.loads_default(x.file.as_deref()));
+        Arc::new(defaults)
+    };
     static ref DEFAULTS: Arc<Mutex<neum::Neum>> = Arc::new(Mutex::new(neum::Neum::default()));
+    /// The files the defaults were defined in
+    static ref DEFAULT_FILES: HashSet<String> =
+        neum::DEFAULT_SOURCES.iter().map(|x| x.0.to_string()).collect();
+    static ref SAFELIST: ClassList = class_list(&OPTIONS.safelist);
+    static ref BLOCKLIST: ClassList = class_list(&OPTIONS.blocklist);
 }
 
-pub fn update(refresh: bool) {
+fn class_list(names: &[String]) -> ClassList {
+    let mut list = ClassList::new();
+    for i in names {
+        if let Err(e) = list.add(i) {
+            eprintln!("Invalid class list entry `{i}`: {e}");
+            std::process::exit(1);
+        }
+    }
+    list
+}
+
+/// If a neum file is in a `.neum` folder
+fn library(path: &Path) -> bool {
+    path.components()
+        .any(|x| x == Component::Normal(OsStr::new(".neum")))
+}
+
+/// Where a rule goes in the css so that the ones that should win come later, rules without
+/// variants are first, then the ones with only selector variants and then at-rules, with the
+/// ones from the defaults before the ones from libraries and then the project's own files
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Order {
+    at_rule: bool,
+    variants: Vec<(u8, String, usize)>,
+    rule: Option<(u8, String, usize)>,
+    class: String,
+}
+
+impl Order {
+    fn new(neum: &neum::Neum, class: &str, block: &Block) -> Order {
+        let position = |rule: &neum::ast::Rule| {
+            let file = rule.file.clone().unwrap_or_default();
+            let layer = if DEFAULT_FILES.contains(&file) {
+                0
+            } else if library(Path::new(&file)) {
+                1
+            } else {
+                2
+            };
+            (layer, file, rule.line)
+        };
+        let (variants, rule) = match neum.rules(class) {
+            Some((variants, rule)) => (
+                variants.into_iter().map(position).collect(),
+                Some(position(rule)),
+            ),
+            None => (Vec::new(), None),
+        };
+        Order {
+            at_rule: matches!(block, Block::AtRule(..)),
+            variants,
+            rule,
+            class: class.to_string(),
+        }
+    }
+}
+
+/// Merges the defaults with the neum files, the files in a `.neum` folder are libraries so the
+/// project's own files are used before them
+pub fn definitions(neum_files: &mut HashMap<PathBuf, neum::Neum>) -> neum::Neum {
+    let mut libraries = neum::Neum::empty();
+    let mut other = neum::Neum::empty();
+
+    for (path, neum) in neum_files.iter_mut().sorted_by(|x, y| x.0.cmp(y.0)) {
+        if library(path) {
+            libraries.combine_priority(neum);
+        } else {
+            other.combine_priority(neum);
+        }
+    }
+
+    let mut total_neum = (*REAL_DEFAULTS.clone()).clone();
+    total_neum.combine_priority(&mut libraries);
+    total_neum.combine_priority(&mut other);
+    total_neum
+}
+
+/// How the css is written from the options
+pub fn format(pretty: bool) -> Format {
+    match pretty {
+        true => Format::Pretty,
+        false => Format::Minify,
+    }
+}
+
+/// What was made the last time the css was updated
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub neum_files: usize,
+    /// The files classes were found in
+    pub html_files: usize,
+    pub classes: usize,
+    /// How many classes were turned into css
+    pub rules: usize,
+    /// The classes that did not match a definition or could not be converted
+    pub unresolved: Vec<Unresolved>,
+}
+
+pub fn update(refresh: bool) -> Summary {
     let mut now = Instant::now();
     let mut output = String::from("/* auto generated by Neum https://github.com/AMTitan/Neum */\n");
     let html = html_parse::HTML_FILES.lock().unwrap();
@@ -22,64 +136,126 @@ pub fn update(refresh: bool) {
     for (_, i) in html.iter() {
         total_classes.append(&mut i.clone());
     }
-    total_classes = total_classes
-        .iter()
-        .unique()
-        .cloned()
-        .collect::<Vec<String>>();
+
+    let mut summary = Summary {
+        neum_files: neum_files.len(),
+        html_files: html.len(),
+        ..Summary::default()
+    };
+    let mut unresolved = Vec::new();
+    let mut rules = Vec::new();
 
     let mut total_neum = DEFAULTS.lock().unwrap();
     if refresh {
-        let mut libraries = neum::Neum::empty();
-        let mut other = neum::Neum::empty();
-
-        for (path, neum) in neum_files.iter_mut() {
-            if path
-                .as_path()
-                .components()
-                .any(|x| x == Component::Normal(OsStr::new(".neum")))
-            {
-                libraries.combine_priority(neum);
-            } else {
-                other.combine_priority(neum);
-            }
-        }
-
-        *total_neum = (*REAL_DEFAULTS.clone()).clone();
-        total_neum.combine_priority(&mut libraries);
-        total_neum.combine_priority(&mut other);
+        *total_neum = definitions(&mut neum_files);
 
-        total_neum.refresh();
+        total_neum.set_matching(match OPTIONS.specific {
+            true => neum::Matching::Specific,
+            false => neum::Matching::First,
+        });
+        total_neum.set_cache_policy(match OPTIONS.cache_size {
+            None => CachePolicy::Unbounded,
+            Some(0) => CachePolicy::Disabled,
+            Some(x) => CachePolicy::Lru(x),
+        });
 
-        if ARGS.verbose {
+        if OPTIONS.verbose {
             println!("Updated neum definitions in {:?}", now.elapsed());
         }
         now = Instant::now();
     }
 
+    total_classes.append(&mut total_neum.select(&SAFELIST));
+    let total_classes = total_classes
+        .into_iter()
+        .unique()
+        .filter(|x| !BLOCKLIST.contains(x))
+        .sorted()
+        .collect::<Vec<String>>();
+    summary.classes = total_classes.len();
+
     for i in total_classes {
-        if let Some(mut x) = total_neum.convert(i.clone()) {
-            while x.starts_with('.') || x.starts_with('@') {
-                let period = x.starts_with('.');
-                let mut split = x.split('}').collect::<Vec<_>>();
-                let mut new_css = format!("{}}}", split.remove(0));
-                if period && new_css.split('{').next().unwrap().contains(':') {
-                    let mut vec = new_css.split(':').collect::<Vec<_>>();
-                    let first = &format!(".{i}");
-                    vec[0] = first;
-                    new_css = vec.join(":");
+        if OPTIONS.ambiguous {
+            let rules = total_neum.ambiguous(&i);
+            if !rules.is_empty() {
+                eprintln!(
+                    "`{i}` could use {}",
+                    rules
+                        .iter()
+                        .map(|x| format!(
+                            "`{}` at {}:{}",
+                            x.name,
+                            x.file.as_deref().unwrap_or(""),
+                            x.line
+                        ))
+                        .join(", ")
+                );
+            }
+        }
+        match total_neum.style(&i) {
+            Ok(Some(block)) => {
+                if !block.is_empty() {
+                    rules.push((Order::new(&total_neum, &i, &block), block));
                 }
-                output.push_str(&new_css);
-                x = split.join("}");
             }
-            if x != ";" {
-                output.push_str(&format!(".{i}{{{x}}}"));
+            Ok(None) => unresolved.push(i),
+            Err(e) => {
+                eprintln!("{e}");
+                unresolved.push(i);
+            }
+        }
+    }
+    rules.sort_by(|x, y| x.0.cmp(&y.0));
+    summary.rules = rules.len();
+    let mut source_map = SourceMap::default();
+    for (_, block) in rules {
+        let (css, origins) =
+            css::render_mapped(std::slice::from_ref(&block), format(OPTIONS.pretty));
+        for (i, origin) in origins {
+            if let Some(file) = origin.file.as_deref().filter(|x| !x.is_empty()) {
+                source_map.add(output.len() + i, file, origin.line);
+            }
+        }
+        output.push_str(&css);
+    }
+    summary.unresolved = report::unresolved(&total_neum, unresolved, &html);
+    if OPTIONS.report {
+        report::print(&summary.unresolved);
+    }
+    if let Some(path) = &OPTIONS.report_json {
+        if let Err(e) = report::write_json(path, &summary.unresolved) {
+            eprintln!("Failed to write {}: {e}", path.display());
+        }
+    }
+    for path in &OPTIONS.output {
+        let mut file = File::create(path).unwrap();
+        file.write_all(output.as_bytes()).unwrap();
+        if OPTIONS.source_map {
+            let mut map = path.clone().into_os_string();
+            map.push(".map");
+            let map = PathBuf::from(map);
+            writeln!(
+                file,
+                "\n/*# sourceMappingURL={} */",
+                map.file_name().unwrap().to_string_lossy()
+            )
+            .unwrap();
+            if let Err(e) = fs::write(&map, source_map.json(&output, &map)) {
+                eprintln!("Failed to write {}: {e}", map.display());
             }
         }
     }
-    let mut file = File::create(ARGS.output.clone().unwrap()).unwrap();
-    file.write_all(output.as_bytes()).unwrap();
-    if ARGS.verbose {
+    if OPTIONS.verbose {
         println!("Generated css in {:?}", now.elapsed());
+        let stats = total_neum.cache_stats();
+        println!(
+            "Cache: {} hits, {} misses ({:.1}% hit rate), {} evictions, {} classes cached",
+            stats.hits,
+            stats.misses,
+            stats.hit_rate() * 100.0,
+            stats.evictions,
+            stats.len
+        );
     }
+    summary
 }
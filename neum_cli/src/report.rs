@@ -0,0 +1,73 @@
+use neum::Neum;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A class from the source code that was not turned into css
+#[derive(Debug, Clone, Serialize)]
+pub struct Unresolved {
+    pub class: String,
+    /// The files the class is used in
+    pub files: Vec<PathBuf>,
+    /// Classes with a similar name that can be converted
+    pub suggestions: Vec<String>,
+}
+
+pub fn unresolved(
+    neum: &Neum,
+    classes: Vec<String>,
+    html: &HashMap<PathBuf, Vec<String>>,
+) -> Vec<Unresolved> {
+    let mut unresolved = classes
+        .into_iter()
+        .map(|class| {
+            let mut files = html
+                .iter()
+                .filter(|x| x.1.contains(&class))
+                .map(|x| x.0.clone())
+                .collect::<Vec<_>>();
+            files.sort();
+            Unresolved {
+                suggestions: neum.suggest(&class),
+                class,
+                files,
+            }
+        })
+        .collect::<Vec<_>>();
+    unresolved.sort_by(|x, y| x.class.cmp(&y.class));
+    unresolved
+}
+
+pub fn print(unresolved: &[Unresolved]) {
+    for i in unresolved {
+        let files = match i.files.is_empty() {
+            // only classes from the safelist are not in any file
+            true => String::from("the safelist"),
+            false => i
+                .files
+                .iter()
+                .map(|x| x.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        eprint!("`{}` in {files} did not match any definition", i.class);
+        match i.suggestions.as_slice() {
+            [] => eprintln!(),
+            suggestions => eprintln!(
+                ", did you mean {}?",
+                suggestions
+                    .iter()
+                    .map(|x| format!("`{x}`"))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+        }
+    }
+}
+
+pub fn write_json(path: &Path, unresolved: &[Unresolved]) -> io::Result<()> {
+    serde_json::to_writer_pretty(File::create(path)?, unresolved)?;
+    Ok(())
+}
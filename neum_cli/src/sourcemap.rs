@@ -0,0 +1,236 @@
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A version 3 source map from each declaration in the css to the line of the neum rule it was
+/// made from
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    /// The name of each source and what is in it for the ones that are not files
+    sources: Vec<(String, Option<&'static str>)>,
+    /// Where the declaration is in the css, which source it is from and the line in that source
+    mappings: Vec<(usize, usize, usize)>,
+}
+
+impl SourceMap {
+    /// Adds a declaration that starts at `offset` in the css, `line` starting at 1
+    pub fn add(&mut self, offset: usize, file: &str, line: usize) {
+        let source = source(file);
+        let index = match self.sources.iter().position(|x| *x == source) {
+            Some(x) => x,
+            None => {
+                self.sources.push(source);
+                self.sources.len() - 1
+            }
+        };
+        self.mappings.push((offset, index, line.saturating_sub(1)));
+    }
+
+    /// The json of the map for `css` written to `path`, the sources that are files are given
+    /// from the folder of the map so that browsers can find them and the defaults are given
+    /// with what is in them
+    pub fn json(&self, css: &str, path: &Path) -> String {
+        let folder = path.parent().unwrap_or(Path::new(""));
+        let sources = self
+            .sources
+            .iter()
+            .map(|(x, content)| match content {
+                Some(_) => x.clone(),
+                None => relative(folder, Path::new(x)).unwrap_or_else(|| x.clone()),
+            })
+            .collect::<Vec<_>>();
+        let mut map = json!({
+            "version": 3,
+            "file": path.file_stem().map(|x| x.to_string_lossy()),
+            "sources": sources,
+            "names": [],
+            "mappings": self.mappings(css),
+        });
+        if self.sources.iter().any(|x| x.1.is_some()) {
+            map["sourcesContent"] = json!(self.sources.iter().map(|x| x.1).collect::<Vec<_>>());
+        }
+        map.to_string()
+    }
+
+    /// Every mapping encoded as base64 VLQs, each line of the css is split by `;` with the
+    /// mappings in it split by `,`, the css is read once from the start to the last mapping
+    fn mappings(&self, css: &str) -> String {
+        let mut mappings = self.mappings.clone();
+        mappings.sort();
+        let mut encoded = String::new();
+        // where in the css has been read up to, with the column there counted in utf-16 as
+        // browsers do
+        let mut position = 0;
+        let mut column = 0;
+        let mut first = true;
+        let mut last = (0, 0, 0);
+        for (offset, source, source_line) in mappings {
+            for x in css[position..offset].chars() {
+                if x == '\n' {
+                    encoded.push(';');
+                    column = 0;
+                    first = true;
+                    last.0 = 0;
+                } else {
+                    column += x.len_utf16() as i64;
+                }
+            }
+            position = offset;
+            if !first {
+                encoded.push(',');
+            }
+            first = false;
+            for i in [
+                column - last.0,
+                source as i64 - last.1,
+                source_line as i64 - last.2,
+                0,
+            ] {
+                vlq(&mut encoded, i);
+            }
+            last = (column, source as i64, source_line as i64);
+        }
+        encoded
+    }
+}
+
+/// The name a file is given in the map along with what is in it if it is one of the defaults,
+/// since those are built into neum and are not where the map can find them
+fn source(file: &str) -> (String, Option<&'static str>) {
+    match neum::DEFAULT_SOURCES.iter().find(|x| x.0 == file) {
+        Some((name, content)) => {
+            let name = Path::new(name).file_name().unwrap_or_default();
+            (
+                format!("neum:defaults/{}", name.to_string_lossy()),
+                Some(content),
+            )
+        }
+        None => (file.to_string(), None),
+    }
+}
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Adds a number as a base64 VLQ, five bits at a time from the lowest with the sign in the
+/// lowest bit
+fn vlq(encoded: &mut String, value: i64) {
+    let mut value = match value < 0 {
+        true => ((-value) << 1) | 1,
+        false => value << 1,
+    };
+    loop {
+        let mut digit = value & 0b11111;
+        value >>= 5;
+        if value > 0 {
+            digit |= 0b100000;
+        }
+        encoded.push(BASE64[digit as usize] as char);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+/// The path to a file from a folder, `None` if the file does not exist
+fn relative(folder: &Path, file: &Path) -> Option<String> {
+    let current = env::current_dir().ok()?;
+    let folder = fs::canonicalize(current.join(folder)).ok()?;
+    let file = fs::canonicalize(file).ok()?;
+    let common = folder
+        .components()
+        .zip(file.components())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let mut path = PathBuf::new();
+    for _ in folder.components().skip(common) {
+        path.push(Component::ParentDir);
+    }
+    for i in file.components().skip(common) {
+        path.push(i);
+    }
+    Some(path.to_string_lossy().replace('\\', "/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(value: i64) -> String {
+        let mut encoded = String::new();
+        vlq(&mut encoded, value);
+        encoded
+    }
+
+    #[test]
+    fn vlq_values() {
+        assert_eq!(encoded(0), "A");
+        assert_eq!(encoded(1), "C");
+        assert_eq!(encoded(-1), "D");
+        assert_eq!(encoded(15), "e");
+        assert_eq!(encoded(16), "gB");
+        assert_eq!(encoded(-16), "hB");
+        assert_eq!(encoded(123), "2H");
+        assert_eq!(encoded(-1000), "x+B");
+    }
+
+    #[test]
+    fn mappings_pretty() {
+        let css = ".a {\n  width: 5px;\n  height: 1px;\n}\n.b {\n  color: red;\n}\n";
+        let mut map = SourceMap::default();
+        map.add(7, "a.neum", 1);
+        map.add(21, "a.neum", 2);
+        map.add(43, "b.neum", 5);
+        assert_eq!(map.mappings(css), ";EAAA;EACA;;;ECGA");
+    }
+
+    #[test]
+    fn mappings_minified() {
+        let css = ".a{width:5px;height:1px}.ü{color:red}";
+        let mut map = SourceMap::default();
+        map.add(28, "b.neum", 5);
+        map.add(3, "a.neum", 1);
+        map.add(13, "a.neum", 2);
+        // the ü is two bytes but one utf-16 unit, so `color` is at column 27
+        assert_eq!(map.mappings(css), "GCAA,UACA,cDGA");
+    }
+
+    #[test]
+    fn default_sources() {
+        let (file, content) = neum::DEFAULT_SOURCES
+            .iter()
+            .find(|x| x.0.ends_with("margin.neum"))
+            .unwrap();
+        let mut map = SourceMap::default();
+        map.add(3, file, 9);
+        map.add(12, "missing/a.neum", 1);
+        let json: serde_json::Value =
+            serde_json::from_str(&map.json(".a{margin:0;width:5px}", Path::new("out.css.map")))
+                .unwrap();
+        assert_eq!(
+            json["sources"],
+            json!(["neum:defaults/margin.neum", "missing/a.neum"])
+        );
+        assert_eq!(json["sourcesContent"], json!([content, null]));
+        assert_eq!(json["file"], "out.css");
+        assert_eq!(json["mappings"], "GAQA,SCRA");
+    }
+
+    #[test]
+    fn relative_paths() {
+        let root = env::temp_dir().join(format!("neum-relative-{}", std::process::id()));
+        fs::create_dir_all(root.join("dist/css")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/a.neum"), "").unwrap();
+        assert_eq!(
+            relative(&root.join("dist/css"), &root.join("src/a.neum")),
+            Some(String::from("../../src/a.neum"))
+        );
+        assert_eq!(
+            relative(&root, &root.join("src/a.neum")),
+            Some(String::from("src/a.neum"))
+        );
+        assert_eq!(relative(&root, &root.join("src/b.neum")), None);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,19 +1,20 @@
 use crate::output::update;
-use crate::ARGS;
+use crate::{config, OPTIONS};
 use notify::RecursiveMode;
 use notify_debouncer_mini::new_debouncer;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::thread;
 use std::time::Duration;
 use walkdir::WalkDir;
 
+use crate::extract::EXTRACTORS;
 use crate::html_parse;
 use crate::neum_parse;
 
 pub fn watch() {
     let current = std::env::current_dir().unwrap();
 
-    if let Some(neum_folder) = &ARGS.neum_folder {
+    if let Some(neum_folder) = &OPTIONS.neum_folder {
         let current = current.clone();
         thread::spawn(move || {
             let (tx, rx) = std::sync::mpsc::channel();
@@ -49,7 +50,7 @@ pub fn watch() {
     debouncer
         .watcher()
         .watch(
-            &ARGS
+            &OPTIONS
                 .source_code
                 .clone()
                 .unwrap_or_else(|| PathBuf::from(".")),
@@ -61,22 +62,21 @@ pub fn watch() {
         let mut changed = false;
         let mut refresh = false;
         for e in event {
-            if !excludes(e.path.clone()) {
-                let e = e.path.strip_prefix(current.clone()).unwrap().to_path_buf();
-                if let Some(extension) = e.extension() {
-                    if extension == "html" || extension == "htm" || extension == "xhtml" {
-                        if html_parse::update_html(e.clone()).is_err() {
-                            eprintln!("Failded to parse {}", e.display());
-                        }
-                        changed = true;
-                    } else if extension == "neum" && ARGS.neum_folder.is_none() {
-                        if let Err(e) = neum_parse::update_neum(e.clone()) {
-                            eprintln!("{e}");
-                        }
-                        changed = true;
-                        refresh = true;
-                    }
+            let e = e.path.strip_prefix(current.clone()).unwrap().to_path_buf();
+            if EXTRACTORS.supported(&e) && !excludes(&e, true) {
+                if html_parse::update_html(e.clone()).is_err() {
+                    eprintln!("Failded to parse {}", e.display());
+                }
+                changed = true;
+            } else if e.extension().is_some_and(|x| x == "neum")
+                && OPTIONS.neum_folder.is_none()
+                && !excludes(&e, false)
+            {
+                if let Err(e) = neum_parse::update_neum(e.clone()) {
+                    eprintln!("{e}");
                 }
+                changed = true;
+                refresh = true;
             }
         }
         if changed {
@@ -85,23 +85,22 @@ pub fn watch() {
     }
 }
 
-pub fn init() {
-    if let Some(neum_folder) = &ARGS.neum_folder {
-        for e in WalkDir::new(neum_folder).into_iter().flatten() {
-            if !excludes(e.path().to_path_buf()) {
-                if let Some(extension) = e.path().extension() {
-                    if extension == "neum" && ARGS.neum_folder.is_none() {
-                        if let Err(e) = neum_parse::update_neum(e.path().to_path_buf()) {
-                            eprintln!("{e}");
-                        }
-                    }
-                }
-            }
+/// Reads every neum file and every file classes can be found in, giving how many of them could not be parsed
+pub fn init() -> usize {
+    let mut failed = 0;
+    for path in neum_parse::neum_paths(
+        OPTIONS.source_code.as_deref(),
+        OPTIONS.neum_folder.as_deref(),
+        &OPTIONS.exclude,
+    ) {
+        if let Err(e) = neum_parse::update_neum(path) {
+            eprintln!("{e}");
+            failed += 1;
         }
     }
 
     for e in WalkDir::new(
-        &ARGS
+        OPTIONS
             .source_code
             .clone()
             .unwrap_or_else(|| PathBuf::from(".")),
@@ -109,28 +108,17 @@ pub fn init() {
     .into_iter()
     .flatten()
     {
-        if !excludes(e.path().to_path_buf()) {
-            if let Some(extension) = e.path().extension() {
-                if extension == "html" || extension == "htm" || extension == "xhtml" {
-                    if html_parse::update_html(e.path().to_path_buf()).is_err() {
-                        eprintln!("Failded to parse {}", e.path().display());
-                    }
-                } else if extension == "neum" && ARGS.neum_folder.is_none() {
-                    if let Err(e) = neum_parse::update_neum(e.path().to_path_buf()) {
-                        eprintln!("{e}");
-                    }
-                }
-            }
+        if EXTRACTORS.supported(e.path())
+            && !excludes(e.path(), true)
+            && html_parse::update_html(e.path().to_path_buf()).is_err()
+        {
+            eprintln!("Failded to parse {}", e.path().display());
+            failed += 1;
         }
     }
-    update(true);
+    failed
 }
 
-fn excludes(path: PathBuf) -> bool {
-    for i in &ARGS.exclude {
-        if path.starts_with(i) {
-            return true;
-        }
-    }
-    false
+fn excludes(path: &Path, source: bool) -> bool {
+    config::excluded(path, &OPTIONS.exclude, source)
 }
@@ -0,0 +1,621 @@
+//! The typed tree that the right hand side of a Neum definition is parsed into
+//! ```
+//! # use neum_parse::ast::*;
+//! let neum = neum_parse::Neum::new("w-{} => width: {*0.25}rem", None).unwrap();
+//! let body = &neum.converts[0].1.body;
+//! assert_eq!(body[0].kind, NodeKind::Text(String::from("width: ")));
+//! assert_eq!(
+//!     body[1].kind,
+//!     NodeKind::Expression(
+//!         Expr::Binary(
+//!             Box::new(Expr::Variable(String::new())),
+//!             Operator::Times,
+//!             Box::new(Expr::Number(0.25))
+//!         ),
+//!         Some(String::from("rem"))
+//!     )
+//! );
+//! ```
+
+use crate::error::{ConvertErrorKind, ErrorType, NeumError};
+use crate::lexer::Token;
+use crate::value::{self, Value};
+use core::slice::Iter;
+use hashbrown::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// A definition, what it is named and the nodes of its right hand side
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// The name as it was written like `w-{}px`
+    pub name: String,
+    pub body: Vec<Node>,
+    pub span: Range<usize>,
+    pub file: Option<String>,
+    pub line: usize,
+}
+
+/// A single piece of a definition along with where it is in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    /// Text that is output as is
+    Text(String),
+    /// A `{}` or `{name}` that is replaced with what the variable captured
+    Variable(String),
+    /// Arithmetic on the captured variables, like `{*0.25}` or `{x / y * 100}`, along with the
+    /// unit written right after it which is only used if the result does not have a unit
+    Expression(Expr, Option<String>),
+    /// A `{{{ }}}` that is replaced with the output of another class
+    Lookup(Vec<Node>),
+    /// A `{{ }}` that is output wrapped in `{` and `}`
+    Block(Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Dimension(f64, String),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+    Function(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Times,
+    Divide,
+}
+
+/// The functions that can be used in an expression, `round`, `floor` and `ceil` can be given a
+/// step to round to as a second argument
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Round,
+    Floor,
+    Ceil,
+    Abs,
+    Min,
+    Max,
+    Clamp,
+}
+
+impl Function {
+    pub fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "round" => Function::Round,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "abs" => Function::Abs,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "clamp" => Function::Clamp,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Round => "round",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Abs => "abs",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Clamp => "clamp",
+        }
+    }
+
+    /// The smallest and largest number of arguments the function takes
+    fn arguments(&self) -> (usize, usize) {
+        match self {
+            Function::Round | Function::Floor | Function::Ceil => (1, 2),
+            Function::Abs => (1, 1),
+            Function::Min | Function::Max => (1, usize::MAX),
+            Function::Clamp => (3, 3),
+        }
+    }
+}
+
+impl Expr {
+    /// Evaluates the expression keeping the units of the values
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> Result<Value, ConvertErrorKind> {
+        match self {
+            Expr::Number(x) => Ok(Value::Dimension(*x, String::new())),
+            Expr::Dimension(x, unit) => Ok(Value::Dimension(*x, unit.clone())),
+            Expr::Variable(x) => {
+                let value = variables.get(x).map_or("", |x| x.as_str());
+                Value::parse(value).ok_or_else(|| ConvertErrorKind::NotANumber(value.to_string()))
+            }
+            Expr::Negate(x) => Ok(x.evaluate(variables)?.negate()),
+            Expr::Binary(left, operator, right) => left
+                .evaluate(variables)?
+                .apply(*operator, &right.evaluate(variables)?),
+            Expr::Function(function, arguments) => Value::call(
+                *function,
+                &arguments
+                    .iter()
+                    .map(|x| x.evaluate(variables))
+                    .collect::<Result<Vec<Value>, ConvertErrorKind>>()?,
+            ),
+        }
+    }
+
+    /// How tightly the expression binds when it is written out, to know when it needs brackets
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(_, Operator::Add | Operator::Subtract, _) => 1,
+            Expr::Binary(..) | Expr::Negate(_) => 2,
+            _ => 3,
+        }
+    }
+}
+
+/// Writes the expression as CSS math, numbers use the precision of the formatter like
+/// [`Value`](crate::value::Value) does
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precision = f.precision().unwrap_or(value::DEFAULT_PRECISION);
+        let bracket = |f: &mut fmt::Formatter, x: &Expr, brackets: bool| match brackets {
+            true => write!(f, "({x:.precision$})"),
+            false => write!(f, "{x:.precision$}"),
+        };
+        match self {
+            Expr::Number(x) => write!(f, "{}", value::number(*x, precision)),
+            Expr::Dimension(x, unit) => write!(f, "{}{unit}", value::number(*x, precision)),
+            Expr::Variable(x) => write!(f, "{{{x}}}"),
+            Expr::Negate(x) => {
+                write!(f, "-1 * ")?;
+                bracket(f, x, x.precedence() < 2)
+            }
+            Expr::Binary(left, operator, right) => {
+                let precedence = self.precedence();
+                bracket(f, left, left.precedence() < precedence)?;
+                write!(
+                    f,
+                    " {} ",
+                    match operator {
+                        Operator::Add => '+',
+                        Operator::Subtract => '-',
+                        Operator::Times => '*',
+                        Operator::Divide => '/',
+                    }
+                )?;
+                bracket(
+                    f,
+                    right,
+                    right.precedence() < precedence
+                        || (right.precedence() == precedence
+                            && matches!(operator, Operator::Subtract | Operator::Divide)),
+                )
+            }
+            Expr::Function(function, arguments) => {
+                write!(f, "{}(", function.name())?;
+                for (i, x) in arguments.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{x:.precision$}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Builds the nodes of a definition from the tokens after the `=>`
+pub fn body(
+    tokens: &[(Token, Range<usize>)],
+    variables: &[String],
+    file: &Option<String>,
+    content: &str,
+) -> Result<Vec<Node>, NeumError> {
+    let mut tokens = tokens.iter();
+    Ok(nodes(&mut tokens, None, variables, file, content)?.0)
+}
+
+/// Builds nodes until the `end` token is found, giving back the nodes and where they end
+fn nodes(
+    tokens: &mut Iter<(Token, Range<usize>)>,
+    end: Option<(Token, Range<usize>)>,
+    variables: &[String],
+    file: &Option<String>,
+    content: &str,
+) -> Result<(Vec<Node>, usize), NeumError> {
+    let mut nodes: Vec<Node> = Vec::new();
+    while let Some((token, span)) = tokens.next() {
+        if let Some((end, _)) = &end {
+            if token == end {
+                return Ok((nodes, span.end));
+            }
+        }
+        let (kind, span) = match token {
+            Token::ReplacementStart => replacement(tokens, span, variables, file, content)?,
+            Token::FullReplacementStart | Token::MultiEqualStart => {
+                let (children, end) = self::nodes(
+                    tokens,
+                    Some(match token {
+                        Token::FullReplacementStart => (Token::FullReplacementEnd, span.clone()),
+                        _ => (Token::MultiEqualEnd, span.clone()),
+                    }),
+                    variables,
+                    file,
+                    content,
+                )?;
+                (
+                    match token {
+                        Token::FullReplacementStart => NodeKind::Lookup(children),
+                        _ => NodeKind::Block(children),
+                    },
+                    span.start..end,
+                )
+            }
+            Token::Space => (NodeKind::Text(" ".to_string()), span.clone()),
+            Token::SemiColon | Token::NewLine => (NodeKind::Text(";".to_string()), span.clone()),
+            Token::Add
+            | Token::Subtract
+            | Token::Times
+            | Token::Divide
+            | Token::Number(_)
+            | Token::String(_) => (
+                NodeKind::Text(content[span.clone()].to_string()),
+                span.clone(),
+            ),
+            _ => {
+                return Err(NeumError::new(
+                    ErrorType::UnexpectedToken,
+                    file.as_deref(),
+                    content,
+                    span.clone(),
+                ))
+            }
+        };
+        match (nodes.last_mut(), kind) {
+            (
+                Some(Node {
+                    kind: NodeKind::Text(last),
+                    span: last_span,
+                }),
+                NodeKind::Text(x),
+            ) => {
+                last.push_str(&x);
+                last_span.end = span.end;
+            }
+            (
+                Some(Node {
+                    kind: NodeKind::Expression(_, default @ None),
+                    span: last_span,
+                }),
+                NodeKind::Text(x),
+            ) if last_span.end == span.start && value::unit(&x).is_some() => {
+                let unit = value::unit(&x).unwrap_or_default();
+                last_span.end += unit.len();
+                *default = Some(unit.to_string());
+                if x.len() > unit.len() {
+                    nodes.push(Node {
+                        kind: NodeKind::Text(x[unit.len()..].to_string()),
+                        span: span.start + unit.len()..span.end,
+                    });
+                }
+            }
+            (_, kind) => nodes.push(Node { kind, span }),
+        }
+    }
+    match end {
+        Some((_, span)) => Err(NeumError::new(
+            ErrorType::UnexpectedEndOfFile,
+            file.as_deref(),
+            content,
+            span,
+        )),
+        None => {
+            let end = nodes.last().map_or(0, |x| x.span.end);
+            Ok((nodes, end))
+        }
+    }
+}
+
+/// Builds a `{}` into either a variable or an expression
+fn replacement(
+    tokens: &mut Iter<(Token, Range<usize>)>,
+    start: &Range<usize>,
+    variables: &[String],
+    file: &Option<String>,
+    content: &str,
+) -> Result<(NodeKind, Range<usize>), NeumError> {
+    let mut inner = Vec::new();
+    let end = loop {
+        match tokens.next() {
+            Some((Token::ReplacementEnd, span)) => break span.clone(),
+            Some((Token::Space, _)) => {}
+            Some((
+                token @ (Token::Add
+                | Token::Subtract
+                | Token::Times
+                | Token::Divide
+                | Token::Number(_)
+                | Token::String(_)),
+                span,
+            )) => inner.push((token, span)),
+            Some((_, span)) => {
+                return Err(NeumError::new(
+                    ErrorType::UnexpectedToken,
+                    file.as_deref(),
+                    content,
+                    span.clone(),
+                ))
+            }
+            None => {
+                return Err(NeumError::new(
+                    ErrorType::UnexpectedEndOfFile,
+                    file.as_deref(),
+                    content,
+                    start.clone(),
+                ))
+            }
+        }
+    };
+    let kind = match inner.as_slice() {
+        [] if variables.contains(&String::new()) => NodeKind::Variable(String::new()),
+        [] => {
+            return Err(NeumError::new(
+                ErrorType::UndefinedVariable,
+                file.as_deref(),
+                content,
+                start.start..end.end,
+            ))
+        }
+        [(Token::String(x), _)] if variables.contains(x) => NodeKind::Variable(x.clone()),
+        _ => NodeKind::Expression(
+            Parser::new(&content[start.end..end.start], start.end, variables)
+                .expression()
+                .map_err(|(error_type, x)| {
+                    NeumError::new(error_type, file.as_deref(), content, x)
+                })?,
+            None,
+        ),
+    };
+    Ok((kind, start.start..end.end))
+}
+
+/// Parses the inside of a `{}`, an expression is numbers, variables and function calls joined by
+/// `+`, `-`, `*` and `/` with the usual precedence and brackets, if it starts with `+`, `*` or `/`
+/// the unnamed variable is used first, as it is for `-` when the definition has an unnamed variable
+/// and otherwise `-` negates what is after it
+/// ```
+/// # use neum_parse::*;
+/// let mut neum = Neum::new("a-{x}-{y} => width: {-(x + y) * 2 + round(x / 3)}px\nb-{} => width: {-1}px", None).unwrap();
+/// assert_eq!(neum.convert("a-4-1"), Some(String::from("width:-9px")));
+/// assert_eq!(neum.convert("b-5"), Some(String::from("width:4px")));
+/// let neum = Neum::new("c-{x}-{y} => width: {x / y}px\nr-{x} => width: {round(3, x)}px", None).unwrap();
+/// assert_eq!(neum.try_convert("c-1-0").unwrap_err().to_string(), "`c-1-0`: rule `c-{x}-{y}` at :1 can not work out `1 / 0`");
+/// assert!(neum.try_convert("r-0").is_err());
+/// let error = Neum::new("a-{x} => width: {(x + 1}", None).unwrap_err();
+/// assert!(error.to_string().starts_with("Error: InvalidExpression :1:23"));
+/// let error = Neum::new("a-{x} => width: {sqrt(x)}", None).unwrap_err();
+/// assert!(error.to_string().starts_with("Error: UnknownFunction :1:17"));
+/// ```
+struct Parser<'a> {
+    source: &'a str,
+    offset: usize,
+    location: usize,
+    variables: &'a [String],
+}
+
+type ParseResult = Result<Expr, (ErrorType, Range<usize>)>;
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, offset: usize, variables: &'a [String]) -> Parser<'a> {
+        Parser {
+            source,
+            offset,
+            location: 0,
+            variables,
+        }
+    }
+
+    fn expression(&mut self) -> ParseResult {
+        let unnamed = self.variables.iter().any(String::is_empty);
+        let value = match self.peek() {
+            Some('+') => {
+                let value = self.variable(String::new(), self.location)?;
+                self.sum(value)?
+            }
+            Some('-') if unnamed => {
+                let value = self.variable(String::new(), self.location)?;
+                self.sum(value)?
+            }
+            Some('*' | '/') => {
+                let value = self.variable(String::new(), self.location)?;
+                let value = self.product(value)?;
+                self.sum(value)?
+            }
+            _ => {
+                let value = self.unary()?;
+                let value = self.product(value)?;
+                self.sum(value)?
+            }
+        };
+        match self.peek() {
+            Some(_) => Err(self.error()),
+            None => Ok(value),
+        }
+    }
+
+    /// Adds or subtracts the terms after `value`
+    fn sum(&mut self, mut value: Expr) -> ParseResult {
+        while let Some(x @ ('+' | '-')) = self.peek() {
+            self.location += 1;
+            let term = self.unary()?;
+            value = Expr::Binary(
+                Box::new(value),
+                Parser::operator(x),
+                Box::new(self.product(term)?),
+            );
+        }
+        Ok(value)
+    }
+
+    /// Multiplies or divides `value` by the factors after it
+    fn product(&mut self, mut value: Expr) -> ParseResult {
+        while let Some(x @ ('*' | '/')) = self.peek() {
+            self.location += 1;
+            value = Expr::Binary(
+                Box::new(value),
+                Parser::operator(x),
+                Box::new(self.unary()?),
+            );
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> ParseResult {
+        if self.peek() != Some('-') {
+            return self.operand();
+        }
+        self.location += 1;
+        Ok(match self.unary()? {
+            Expr::Number(x) => Expr::Number(-x),
+            Expr::Dimension(x, unit) => Expr::Dimension(-x, unit),
+            x => Expr::Negate(Box::new(x)),
+        })
+    }
+
+    /// A whole expression in brackets, to be used inside of brackets or a function call
+    fn inner(&mut self) -> ParseResult {
+        let value = self.unary()?;
+        let value = self.product(value)?;
+        self.sum(value)
+    }
+
+    fn operand(&mut self) -> ParseResult {
+        let next = self.peek();
+        let start = self.location;
+        match next {
+            Some(x) if x.is_ascii_digit() || x == '.' => {
+                self.take_while(|x| x.is_ascii_digit() || x == '.');
+                let number = self.location;
+                self.take_while(|x| x.is_ascii_alphabetic() || x == '%');
+                let error = (
+                    ErrorType::InvalidExpression,
+                    self.offset + start..self.offset + self.location,
+                );
+                let value = self.source[start..number]
+                    .parse()
+                    .map_err(|_| error.clone())?;
+                match &self.source[number..self.location] {
+                    "" => Ok(Expr::Number(value)),
+                    unit if value::unit(unit) == Some(unit) => {
+                        Ok(Expr::Dimension(value, unit.to_lowercase()))
+                    }
+                    _ => Err(error),
+                }
+            }
+            Some('(') => {
+                self.location += 1;
+                let value = self.inner()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(x) if x.is_alphabetic() || x == '_' => {
+                self.take_while(|x| x.is_alphanumeric() || x == '_');
+                let name = &self.source[start..self.location];
+                if self.peek() == Some('(') {
+                    self.call(name, start)
+                } else {
+                    self.variable(name.to_string(), start)
+                }
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    /// Parses the arguments of a function after its name
+    fn call(&mut self, name: &str, start: usize) -> ParseResult {
+        let function = Function::from_name(name).ok_or((
+            ErrorType::UnknownFunction,
+            self.offset + start..self.offset + start + name.len(),
+        ))?;
+        self.location += 1;
+        let mut arguments = vec![self.inner()?];
+        while self.peek() == Some(',') {
+            self.location += 1;
+            arguments.push(self.inner()?);
+        }
+        self.expect(')')?;
+        let (min, max) = function.arguments();
+        if arguments.len() < min || arguments.len() > max {
+            return Err((
+                ErrorType::InvalidExpression,
+                self.offset + start..self.offset + self.location,
+            ));
+        }
+        Ok(Expr::Function(function, arguments))
+    }
+
+    /// Makes sure that a variable used was captured in the name
+    fn variable(&self, name: String, start: usize) -> ParseResult {
+        if self.variables.contains(&name) {
+            Ok(Expr::Variable(name))
+        } else {
+            Err((
+                ErrorType::UndefinedVariable,
+                self.offset + start..self.offset + start + name.len().max(1),
+            ))
+        }
+    }
+
+    fn operator(x: char) -> Operator {
+        match x {
+            '+' => Operator::Add,
+            '-' => Operator::Subtract,
+            '*' => Operator::Times,
+            _ => Operator::Divide,
+        }
+    }
+
+    fn expect(&mut self, x: char) -> Result<(), (ErrorType, Range<usize>)> {
+        if self.peek() == Some(x) {
+            self.location += 1;
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Skips white space and gives the next char
+    fn peek(&mut self) -> Option<char> {
+        self.take_while(char::is_whitespace);
+        self.source[self.location..].chars().next()
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) {
+        while let Some(x) = self.source[self.location..].chars().next() {
+            if !f(x) {
+                break;
+            }
+            self.location += x.len_utf8();
+        }
+    }
+
+    fn error(&self) -> (ErrorType, Range<usize>) {
+        let length = self.source[self.location..]
+            .chars()
+            .next()
+            .map(char::len_utf8)
+            .unwrap_or(1);
+        (
+            ErrorType::InvalidExpression,
+            self.offset + self.location..self.offset + self.location + length,
+        )
+    }
+}
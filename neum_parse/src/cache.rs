@@ -0,0 +1,335 @@
+//! The cache of converted classes, it is split into shards that are locked on their own so that
+//! many threads can convert classes with the same [`Neum`](crate::Neum) at once
+//! ```
+//! # use neum_parse::cache::Cache;
+//! # use neum_parse::css::Block;
+//! let width = vec![Block::declaration("width: 5px", None)];
+//! let cache = Cache::new();
+//! cache.insert(String::from("w-5"), Some(width.clone()), Vec::new());
+//! cache.insert(String::from("nope"), None, Vec::new());
+//! assert_eq!(cache.get("w-5"), Some(Some(width)));
+//! assert_eq!(cache.get("nope"), Some(None));
+//! assert_eq!(cache.get("w-6"), None);
+//! assert_eq!(cache.len(), 2);
+//! ```
+
+use crate::css::Block;
+use hashbrown::hash_map::DefaultHashBuilder;
+use hashbrown::{HashMap, HashSet};
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// How many shards the cache is split into
+const SHARDS: usize = 16;
+
+/// How much the cache keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Every class that is converted is kept
+    #[default]
+    Unbounded,
+    /// At most this many classes are kept, the ones that were used the longest time ago are
+    /// dropped first, each shard keeps its own share of the capacity so this is close to but not
+    /// exactly least recently used
+    /// ```
+    /// # use neum_parse::cache::{Cache, CachePolicy};
+    /// let cache = Cache::with_policy(CachePolicy::Lru(1));
+    /// cache.insert(String::from("a"), None, Vec::new());
+    /// cache.insert(String::from("b"), None, Vec::new());
+    /// assert_eq!(cache.get("a"), None);
+    /// assert_eq!(cache.get("b"), Some(None));
+    /// assert_eq!(cache.stats().evictions, 1);
+    /// ```
+    Lru(usize),
+    /// Nothing is kept and every class is converted each time
+    Disabled,
+}
+
+/// How well the cache has been working since it was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Classes that were dropped to make room for others
+    pub evictions: u64,
+    /// How many classes are in the cache right now
+    pub len: usize,
+}
+
+impl CacheStats {
+    /// The share of lookups that were in the cache, from 0 to 1
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Entry {
+    output: Option<Vec<Block>>,
+    /// The classes that were looked up to make the output
+    lookups: Vec<String>,
+    /// When it was last used
+    tick: u64,
+}
+
+#[derive(Clone, Default)]
+struct Shard {
+    classes: HashMap<String, Entry>,
+    /// The classes by when they were last used, only kept for [`CachePolicy::Lru`]
+    used: BTreeMap<u64, String>,
+    tick: u64,
+    capacity: Option<usize>,
+}
+
+impl Shard {
+    /// Marks a class as just used
+    fn touch(&mut self, input: &str) {
+        self.tick += 1;
+        if let Some(entry) = self.classes.get_mut(input) {
+            let key = self.used.remove(&entry.tick).unwrap_or_default();
+            entry.tick = self.tick;
+            self.used.insert(self.tick, key);
+        }
+    }
+}
+
+pub struct Cache {
+    shards: Box<[RwLock<Shard>]>,
+    hasher: DefaultHashBuilder,
+    policy: CachePolicy,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Cache {
+    pub fn new() -> Cache {
+        Cache::with_policy(CachePolicy::Unbounded)
+    }
+
+    pub fn with_policy(policy: CachePolicy) -> Cache {
+        let shards = match policy {
+            CachePolicy::Unbounded => SHARDS,
+            // so that every shard can hold at least one class
+            CachePolicy::Lru(capacity) => capacity.clamp(1, SHARDS),
+            CachePolicy::Disabled => 1,
+        };
+        Cache {
+            shards: (0..shards)
+                .map(|x| {
+                    RwLock::new(Shard {
+                        capacity: match policy {
+                            CachePolicy::Lru(capacity) => {
+                                Some(capacity / shards + usize::from(x < capacity % shards))
+                            }
+                            _ => None,
+                        },
+                        ..Shard::default()
+                    })
+                })
+                .collect(),
+            hasher: DefaultHashBuilder::default(),
+            policy,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn policy(&self) -> CachePolicy {
+        self.policy
+    }
+
+    /// Gives what a class was converted to, `Some(None)` if it was known to not be a class and
+    /// `None` if it has not been converted yet
+    pub fn get(&self, input: &str) -> Option<Option<Vec<Block>>> {
+        let shard = self.shard(input);
+        let output = match self.policy {
+            CachePolicy::Unbounded => self
+                .read(shard)
+                .classes
+                .get(input)
+                .map(|x| x.output.clone()),
+            CachePolicy::Lru(_) => {
+                let mut shard = self.write(shard);
+                let output = shard.classes.get(input).map(|x| x.output.clone());
+                if output.is_some() {
+                    shard.touch(input);
+                }
+                output
+            }
+            CachePolicy::Disabled => None,
+        };
+        match output {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+        output
+    }
+
+    /// Keeps what a class was converted to along with the other classes that were looked up to
+    /// convert it, so that it can be dropped when they are
+    pub fn insert(&self, input: String, output: Option<Vec<Block>>, lookups: Vec<String>) {
+        if self.policy == CachePolicy::Disabled {
+            return;
+        }
+        let mut shard = self.write(self.shard(&input));
+        if let Some(entry) = shard.classes.get_mut(&input) {
+            entry.output = output;
+            entry.lookups = lookups;
+            shard.touch(&input);
+            return;
+        }
+        if let Some(capacity) = shard.capacity {
+            while shard.classes.len() >= capacity {
+                match shard.used.pop_first() {
+                    Some((_, oldest)) => {
+                        shard.classes.remove(&oldest);
+                        self.evictions.fetch_add(1, Ordering::Relaxed);
+                    }
+                    None => return,
+                }
+            }
+            shard.tick += 1;
+            let tick = shard.tick;
+            shard.used.insert(tick, input.clone());
+            shard.classes.insert(
+                input,
+                Entry {
+                    output,
+                    lookups,
+                    tick,
+                },
+            );
+        } else {
+            shard.classes.insert(
+                input,
+                Entry {
+                    output,
+                    lookups,
+                    tick: 0,
+                },
+            );
+        }
+    }
+
+    /// Drops every class that `affected` gives true for along with every class that looked any of
+    /// them up, giving how many were dropped
+    /// ```
+    /// # use neum_parse::cache::Cache;
+    /// # use neum_parse::css::Block;
+    /// let cache = Cache::new();
+    /// cache.insert(String::from("ml-4"), Some(vec![Block::declaration("margin-left: 1rem", None)]), Vec::new());
+    /// cache.insert(String::from("mx-4"), Some(Vec::new()), vec![String::from("ml-4")]);
+    /// cache.insert(String::from("w-4"), Some(vec![Block::declaration("width: 1rem", None)]), Vec::new());
+    /// assert_eq!(cache.remove_where(|input, _| input == "ml-4"), 2);
+    /// assert_eq!(cache.get("mx-4"), None);
+    /// assert!(cache.get("w-4").is_some());
+    /// ```
+    pub fn remove_where(&self, affected: impl Fn(&str, Option<&[Block]>) -> bool) -> usize {
+        // every shard is locked so that nothing can be converted from a class while it is dropped
+        let mut shards = (0..self.shards.len())
+            .map(|x| self.write(x))
+            .collect::<Vec<_>>();
+        let mut removed = HashSet::new();
+        for shard in &shards {
+            for (input, entry) in &shard.classes {
+                if affected(input, entry.output.as_deref()) {
+                    removed.insert(input.clone());
+                }
+            }
+        }
+        loop {
+            let count = removed.len();
+            for shard in &shards {
+                for (input, entry) in &shard.classes {
+                    if !removed.contains(input) && entry.lookups.iter().any(|x| removed.contains(x))
+                    {
+                        removed.insert(input.clone());
+                    }
+                }
+            }
+            if removed.len() == count {
+                break;
+            }
+        }
+        for shard in shards.iter_mut() {
+            shard.classes.retain(|x, _| !removed.contains(x));
+            shard.used.retain(|_, x| !removed.contains(x));
+        }
+        removed.len()
+    }
+
+    /// How many classes are in the cache
+    pub fn len(&self) -> usize {
+        (0..self.shards.len())
+            .map(|x| self.read(x).classes.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            len: self.len(),
+        }
+    }
+
+    fn shard(&self, input: &str) -> usize {
+        self.hasher.hash_one(input) as usize % self.shards.len()
+    }
+
+    // the cache is only ever a copy of what a class converts to, so a panic while it was locked
+    // can not leave it wrong
+    fn read(&self, shard: usize) -> RwLockReadGuard<'_, Shard> {
+        self.shards[shard].read().unwrap_or_else(|x| x.into_inner())
+    }
+
+    fn write(&self, shard: usize) -> RwLockWriteGuard<'_, Shard> {
+        self.shards[shard]
+            .write()
+            .unwrap_or_else(|x| x.into_inner())
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache::new()
+    }
+}
+
+/// Copies everything in the cache, so that changing the copy does not change the original
+impl Clone for Cache {
+    fn clone(&self) -> Self {
+        Cache {
+            shards: (0..self.shards.len())
+                .map(|x| RwLock::new(self.read(x).clone()))
+                .collect(),
+            hasher: self.hasher.clone(),
+            policy: self.policy,
+            hits: AtomicU64::new(self.hits.load(Ordering::Relaxed)),
+            misses: AtomicU64::new(self.misses.load(Ordering::Relaxed)),
+            evictions: AtomicU64::new(self.evictions.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl std::fmt::Debug for Cache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Cache")
+            .field("policy", &self.policy)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
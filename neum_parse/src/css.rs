@@ -0,0 +1,431 @@
+//! Helpers for turning Neum output into valid CSS
+
+/// Escapes a class name so that it can be used as a CSS selector, the same way as `CSS.escape`
+/// does in browsers
+/// ```
+/// # use neum_parse::css::escape;
+/// assert_eq!(escape("w-5"), "w-5");
+/// assert_eq!(escape("mt--4"), "mt--4");
+/// assert_eq!(escape("w-50%"), r"w-50\%");
+/// assert_eq!(escape("h-6.5"), r"h-6\.5");
+/// assert_eq!(escape("w-1/2"), r"w-1\/2");
+/// assert_eq!(escape("md:hover:bg-red"), r"md\:hover\:bg-red");
+/// assert_eq!(escape("bg-[#ff0000]"), r"bg-\[\#ff0000\]");
+/// assert_eq!(escape("w-[calc(100%,1rem)]"), r"w-\[calc\(100\%\,1rem\)\]");
+/// assert_eq!(escape("2xl:w-5"), r"\32 xl\:w-5");
+/// assert_eq!(escape("-2"), r"-\32 ");
+/// assert_eq!(escape("-"), r"\-");
+/// assert_eq!(escape("a\tb\0"), "a\\9 b\u{FFFD}");
+/// assert_eq!(escape("ü-5"), "ü-5");
+/// ```
+pub fn escape<S: AsRef<str>>(class: S) -> String {
+    let class = class.as_ref();
+    let mut escaped = String::new();
+    for (i, x) in class.chars().enumerate() {
+        match x {
+            '\0' => escaped.push('\u{FFFD}'),
+            '\u{1}'..='\u{1F}' | '\u{7F}' => escaped.push_str(&format!("\\{:x} ", x as u32)),
+            '0'..='9' if i == 0 || (i == 1 && class.starts_with('-')) => {
+                escaped.push_str(&format!("\\{:x} ", x as u32))
+            }
+            '-' if i == 0 && class.len() == 1 => escaped.push_str("\\-"),
+            x if x.is_ascii_alphanumeric() || x == '-' || x == '_' || !x.is_ascii() => {
+                escaped.push(x)
+            }
+            x => {
+                escaped.push('\\');
+                escaped.push(x);
+            }
+        }
+    }
+    escaped
+}
+
+/// Where a declaration was written, the file and line of the definition it is from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Origin {
+    pub file: Option<String>,
+    /// Starting at 1
+    pub line: usize,
+}
+
+/// A part of a stylesheet, the text in each one has its spaces made into one
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    /// A declaration like `width: 5px` without the `;`, along with where it was written if that
+    /// is known
+    Declaration(String, Option<Origin>),
+    /// A selector and the blocks in it
+    Rule(String, Vec<Block>),
+    /// An at-rule like `@media (min-width: 768px)` wrapping the blocks in it
+    AtRule(String, Vec<Block>),
+}
+
+impl Block {
+    /// A declaration with its spaces made into one and written as `name: value`, a `;` or `{` in
+    /// it is kept as part of it
+    /// ```
+    /// # use neum_parse::css::Block;
+    /// assert_eq!(
+    ///     Block::declaration("content :  \"a;  b\" ", None),
+    ///     Block::Declaration(String::from("content: \"a;  b\""), None)
+    /// );
+    /// ```
+    pub fn declaration<S: AsRef<str>>(text: S, origin: Option<Origin>) -> Block {
+        Block::Declaration(statement(&pieces(text.as_ref()), true, false), origin)
+    }
+
+    /// A rule with its spaces made into one, being an at-rule if it starts with `@`
+    /// ```
+    /// # use neum_parse::css::Block;
+    /// assert_eq!(Block::rule(" &:hover ", Vec::new()), Block::Rule(String::from("&:hover"), Vec::new()));
+    /// assert_eq!(Block::rule("@media  print", Vec::new()), Block::AtRule(String::from("@media print"), Vec::new()));
+    /// ```
+    pub fn rule<S: AsRef<str>>(prelude: S, blocks: Vec<Block>) -> Block {
+        let prelude = statement(&pieces(prelude.as_ref()), false, false);
+        match prelude.starts_with('@') {
+            true => Block::AtRule(prelude, blocks),
+            false => Block::Rule(prelude, blocks),
+        }
+    }
+
+    /// If there are no declarations in it
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Block::Declaration(..) => false,
+            Block::Rule(_, blocks) | Block::AtRule(_, blocks) => blocks.iter().all(Block::is_empty),
+        }
+    }
+}
+
+/// How css is written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Without anything that is not needed
+    #[default]
+    Minify,
+    /// With each rule and declaration on its own line, indented by how deep it is
+    Pretty,
+}
+
+/// Reads css into blocks, `{`, `;` and `}` in strings, `url()`s and comments are skipped and any
+/// blocks that are not closed are closed at the end
+/// ```
+/// # use neum_parse::css::{parse, Block};
+/// assert_eq!(
+///     parse("@media (min-width:768px){.a:hover{content: \"}\";  width :5px}}color: red;"),
+///     vec![
+///         Block::AtRule(String::from("@media (min-width:768px)"), vec![Block::Rule(
+///             String::from(".a:hover"),
+///             vec![
+///                 Block::Declaration(String::from("content: \"}\""), None),
+///                 Block::Declaration(String::from("width: 5px"), None)
+///             ]
+///         )]),
+///         Block::Declaration(String::from("color: red"), None)
+///     ]
+/// );
+/// ```
+pub fn parse<S: AsRef<str>>(css: S) -> Vec<Block> {
+    let mut open: Vec<(String, Vec<Block>)> = Vec::new();
+    let mut blocks = Vec::new();
+    for Statement { pieces, end } in statements(css.as_ref()) {
+        let current = match open.last_mut() {
+            Some(x) => &mut x.1,
+            None => &mut blocks,
+        };
+        if end == Some('{') {
+            open.push((statement(&pieces, false, false), Vec::new()));
+            continue;
+        }
+        if pieces
+            .iter()
+            .any(|x| matches!(x, Piece::Char(_) | Piece::Raw(_)))
+        {
+            current.push(Block::Declaration(statement(&pieces, true, false), None));
+        }
+        if end == Some('}') {
+            close(&mut open, &mut blocks);
+        }
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut blocks);
+    }
+    blocks
+}
+
+/// Closes the block that was opened last, putting it in the one it is in
+fn close(open: &mut Vec<(String, Vec<Block>)>, blocks: &mut Vec<Block>) {
+    let Some((prelude, children)) = open.pop() else {
+        return;
+    };
+    let block = Block::rule(prelude, children);
+    match open.last_mut() {
+        Some(x) => x.1.push(block),
+        None => blocks.push(block),
+    }
+}
+
+/// Writes out blocks as css
+/// ```
+/// # use neum_parse::css::{render, Block, Format};
+/// let blocks = vec![Block::AtRule(
+///     String::from("@media (min-width: 768px)"),
+///     vec![Block::Rule(String::from(r".md\:w-5"), vec![Block::Declaration(String::from("width: 5px"), None)])],
+/// )];
+/// assert_eq!(render(&blocks, Format::Minify), r"@media (min-width:768px){.md\:w-5{width:5px}}");
+/// assert_eq!(render(&blocks, Format::Pretty), "@media (min-width: 768px) {\n  .md\\:w-5 {\n    width: 5px;\n  }\n}\n");
+/// ```
+pub fn render(blocks: &[Block], format: Format) -> String {
+    render_mapped(blocks, format).0
+}
+
+/// The same as [`render`] along with where each declaration that has an origin starts in the css
+/// ```
+/// # use neum_parse::css::{render_mapped, Block, Format, Origin};
+/// let origin = Origin { file: None, line: 2 };
+/// let blocks = vec![Block::Rule(String::from(".a"), vec![
+///     Block::Declaration(String::from("color: red"), None),
+///     Block::Declaration(String::from("width: 5px"), Some(origin.clone())),
+/// ])];
+/// assert_eq!(render_mapped(&blocks, Format::Minify), (String::from(".a{color:red;width:5px}"), vec![(13, &origin)]));
+/// assert_eq!(render_mapped(&blocks, Format::Pretty).1, vec![(21, &origin)]);
+/// ```
+pub fn render_mapped(blocks: &[Block], format: Format) -> (String, Vec<(usize, &Origin)>) {
+    let mut css = String::new();
+    let mut origins = Vec::new();
+    write(&mut css, &mut origins, blocks, format, 0);
+    (css, origins)
+}
+
+fn write<'a>(
+    css: &mut String,
+    origins: &mut Vec<(usize, &'a Origin)>,
+    blocks: &'a [Block],
+    format: Format,
+    depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    for (i, block) in blocks.iter().enumerate() {
+        if let Block::Declaration(_, Some(origin)) = block {
+            let start = match format {
+                Format::Minify => css.len(),
+                Format::Pretty => css.len() + indent.len(),
+            };
+            origins.push((start, origin));
+        }
+        match (block, format) {
+            (Block::Declaration(x, _), Format::Minify) => {
+                css.push_str(&minified(x, true));
+                if i + 1 < blocks.len() {
+                    css.push(';');
+                }
+            }
+            (Block::Declaration(x, _), Format::Pretty) => css.push_str(&format!("{indent}{x};\n")),
+            (Block::Rule(prelude, blocks) | Block::AtRule(prelude, blocks), Format::Minify) => {
+                css.push_str(&minified(prelude, false));
+                css.push('{');
+                write(css, origins, blocks, format, depth + 1);
+                css.push('}');
+            }
+            (Block::Rule(prelude, blocks) | Block::AtRule(prelude, blocks), Format::Pretty) => {
+                css.push_str(&format!("{indent}{prelude} {{\n"));
+                write(css, origins, blocks, format, depth + 1);
+                css.push_str(&format!("{indent}}}\n"));
+            }
+        }
+    }
+}
+
+/// Minifies the text of a declaration or a selector
+fn minified(text: &str, declaration: bool) -> String {
+    statement(&pieces(text), declaration, true)
+}
+
+/// Removes every space and comment that is not needed, strings, `url()`s and escaped characters are
+/// left as they are
+/// ```
+/// # use neum_parse::css::minify;
+/// assert_eq!(
+///     minify(".a , .b { color : red ;\n  margin: 0 auto; }"),
+///     ".a,.b{color:red;margin:0 auto}"
+/// );
+/// assert_eq!(
+///     minify(r#".q::before{content: "a; b: c { d";} /* x */ .i{background: url(a; b.png);}"#),
+///     r#".q::before{content:"a; b: c { d"}.i{background:url(a; b.png)}"#
+/// );
+/// assert_eq!(minify(r".md\:w-5 :hover{width: calc(1px + 2rem)}"), r".md\:w-5 :hover{width:calc(1px + 2rem)}");
+/// assert_eq!(minify(".a { color: red; &:hover { color: blue; } }"), ".a{color:red;&:hover{color:blue}}");
+/// ```
+pub fn minify<S: AsRef<str>>(css: S) -> String {
+    render(&parse(css), Format::Minify)
+}
+
+/// Puts each rule and declaration on its own line, indented by how deep it is
+/// ```
+/// # use neum_parse::css::pretty;
+/// assert_eq!(
+///     pretty("@media (min-width:768px){.md\\:w-5{width:5px;height : 1px}}.a,.b{content:\"{ x; }\"}"),
+///     "@media (min-width:768px) {\n  .md\\:w-5 {\n    width: 5px;\n    height: 1px;\n  }\n}\n.a,.b {\n  content: \"{ x; }\";\n}\n"
+/// );
+/// ```
+pub fn pretty<S: AsRef<str>>(css: S) -> String {
+    render(&parse(css), Format::Pretty)
+}
+
+/// A part of some css, `Raw` being strings, `url()`s, escaped characters and comments that are
+/// never changed
+#[derive(Debug, PartialEq)]
+enum Piece<'a> {
+    Char(char),
+    Space,
+    Raw(&'a str),
+    Comment(&'a str),
+}
+
+/// The text before a `{`, `;` or `}`
+struct Statement<'a> {
+    pieces: Vec<Piece<'a>>,
+    /// Which one it was, `None` for text at the end without any of them
+    end: Option<char>,
+}
+
+/// Splits css at each `{`, `;` and `}`
+fn statements(css: &str) -> Vec<Statement<'_>> {
+    let mut statements = Vec::new();
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    while let Some(x) = css[i..].chars().next() {
+        if let '{' | ';' | '}' = x {
+            statements.push(Statement {
+                pieces: std::mem::take(&mut pieces),
+                end: Some(x),
+            });
+            i += 1;
+            continue;
+        }
+        let (piece, length) = piece(&css[i..], x);
+        pieces.push(piece);
+        i += length;
+    }
+    if !pieces.is_empty() {
+        statements.push(Statement { pieces, end: None });
+    }
+    statements
+}
+
+/// Every piece of some css without splitting it
+fn pieces(css: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    while let Some(x) = css[i..].chars().next() {
+        let (piece, length) = piece(&css[i..], x);
+        pieces.push(piece);
+        i += length;
+    }
+    pieces
+}
+
+/// The piece at the start of some css, which starts with `x`, and how long it is
+fn piece(rest: &str, x: char) -> (Piece<'_>, usize) {
+    match x {
+        '"' | '\'' => {
+            let length = string(rest, x);
+            (Piece::Raw(&rest[..length]), length)
+        }
+        '\\' => {
+            let length = rest[1..].chars().next().map_or(0, char::len_utf8) + 1;
+            (Piece::Raw(&rest[..length]), length)
+        }
+        '/' if rest.starts_with("/*") => {
+            let length = rest[2..].find("*/").map_or(rest.len(), |x| x + 4);
+            (Piece::Comment(&rest[..length]), length)
+        }
+        x if x.is_whitespace() => {
+            let length = rest
+                .find(|x: char| !x.is_whitespace())
+                .unwrap_or(rest.len());
+            (Piece::Space, length)
+        }
+        _ => match url(rest) {
+            Some(length) => (Piece::Raw(&rest[..length]), length),
+            None => (Piece::Char(x), x.len_utf8()),
+        },
+    }
+}
+
+/// The length of a string up to and including its closing quote
+fn string(text: &str, quote: char) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, x)) = chars.next() {
+        match x {
+            '\\' => {
+                chars.next();
+            }
+            '\n' => return i,
+            x if x == quote => return i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// The length of a `url()` without quotes, since what is in it can not be changed
+fn url(text: &str) -> Option<usize> {
+    let inner = text
+        .get(..4)
+        .filter(|x| x.eq_ignore_ascii_case("url("))
+        .map(|_| &text[4..])?;
+    if inner.trim_start().starts_with(['"', '\'']) {
+        return None;
+    }
+    inner.find(')').map(|x| x + 5)
+}
+
+/// Writes out the pieces of a statement with each run of spaces made into one, leaving out the
+/// ones that are not needed when minifying, a declaration is written as `name: value` or
+/// `name:value`
+fn statement(pieces: &[Piece], declaration: bool, minify: bool) -> String {
+    let mut text = String::new();
+    let mut last = None;
+    let mut space = false;
+    let mut colon = false;
+    for piece in pieces {
+        let next = match piece {
+            Piece::Space => {
+                space = true;
+                continue;
+            }
+            Piece::Comment(_) if minify => {
+                space = true;
+                continue;
+            }
+            Piece::Char(x) => Some(*x),
+            Piece::Raw(_) | Piece::Comment(_) => None,
+        };
+        if space && !text.is_empty() && !text.ends_with(' ') {
+            let unneeded = minify
+                && (matches!(last, Some(',' | '(' | ':')) || matches!(next, Some(',' | ')')))
+                || declaration && !colon && next == Some(':');
+            if !unneeded {
+                text.push(' ');
+            }
+        }
+        space = false;
+        match piece {
+            Piece::Char(x) => {
+                text.push(*x);
+                if *x == ':' && declaration && !colon {
+                    colon = true;
+                    if !minify {
+                        text.push(' ');
+                    }
+                }
+            }
+            Piece::Raw(x) | Piece::Comment(x) => text.push_str(x),
+            Piece::Space => {}
+        }
+        last = next;
+    }
+    text
+}
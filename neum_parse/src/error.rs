@@ -1,12 +1,18 @@
+use crate::ast::{Node, Rule};
 use std::fmt;
 use std::ops::Range;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ErrorType {
     UnexpectedEndOfFile,
     UnexpectedToken,
     NoStartingMultiComment,
     VariableMultiDefine,
+    InvalidExpression,
+    UndefinedVariable,
+    UnknownFunction,
+    UnknownType,
+    InvalidRegex,
 }
 
 pub struct NeumError {
@@ -81,6 +87,99 @@ impl NeumError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertErrorKind {
+    /// A variable used in arithmetic captured something that is not a number
+    NotANumber(String),
+    /// Arithmetic was done on two values whose units can not be used together, an empty unit
+    /// being a plain number
+    MixedUnits(String, String),
+    /// A function like `round` was used on a `calc()` that can not be worked out
+    NotReducible(String, String),
+    /// Arithmetic gave a value that can not be written in CSS, like dividing by zero
+    NotFinite(String),
+}
+
+/// An error from a definition that matched but could not be converted
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertError {
+    /// The class that was being converted
+    pub input: String,
+    /// The name of the definition that failed
+    pub rule: String,
+    pub file: Option<String>,
+    pub line: usize,
+    /// Where in the file the part of the definition that failed is
+    pub span: Range<usize>,
+    /// The class that was being looked up when it failed, if it was not the input
+    pub lookup: Option<String>,
+    pub kind: ConvertErrorKind,
+}
+
+impl ConvertError {
+    pub fn new<S: AsRef<str>>(
+        input: S,
+        rule: &Rule,
+        node: &Node,
+        kind: ConvertErrorKind,
+    ) -> ConvertError {
+        ConvertError {
+            input: input.as_ref().to_string(),
+            rule: rule.name.clone(),
+            file: rule.file.clone(),
+            line: rule.line,
+            span: node.span.clone(),
+            lookup: None,
+            kind,
+        }
+    }
+
+    /// Makes an error from converting a lookup into an error of the class that looked it up
+    pub fn within<S: AsRef<str>>(mut self: Box<Self>, input: S) -> Box<ConvertError> {
+        if self.lookup.is_none() {
+            self.lookup = Some(self.input.clone());
+        }
+        self.input = input.as_ref().to_string();
+        self
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}`: rule `{}` at {}:{}",
+            self.input,
+            self.rule,
+            match &self.file {
+                Some(x) => x,
+                None => "",
+            },
+            self.line
+        )?;
+        if let Some(lookup) = &self.lookup {
+            write!(f, " (looking up `{lookup}`)")?;
+        }
+        match &self.kind {
+            ConvertErrorKind::NotANumber(x) => write!(f, " needs a number, got `{x}`"),
+            ConvertErrorKind::MixedUnits(x, y) => {
+                write!(f, " can not mix {} and {}", unit_name(x), unit_name(y))
+            }
+            ConvertErrorKind::NotReducible(x, y) => write!(f, " can not use `{x}` on `{y}`"),
+            ConvertErrorKind::NotFinite(x) => write!(f, " can not work out `{x}`"),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+fn unit_name(unit: &str) -> String {
+    match unit {
+        "" => "a number".to_string(),
+        x => format!("`{x}`"),
+    }
+}
+
 pub fn get_loc(content: &str, location: usize) -> Option<(usize, usize)> {
     let mut y = 0;
     let mut current = 0;
@@ -0,0 +1,99 @@
+//! Where the css of a class came from, made by [`Neum::explain`](crate::Neum::explain)
+//! ```
+//! # use neum_parse::*;
+//! let neum = Neum::new("m-{} => margin: {*0.25}rem\nmx-{} => {{{ ml-{} }}}; {{{ mr-{} }}}\nml-{} => margin-left: {*0.25}rem\nmr-{} => margin-right: {*0.25}rem", Some("margin.neum")).unwrap();
+//! let explanation = neum.explain("mx-4");
+//! assert_eq!(explanation.rule.as_ref().map(|x| x.line), Some(2));
+//! assert_eq!(explanation.lookups.iter().map(|x| x.input.as_str()).collect::<Vec<_>>(), vec!["ml-4", "mr-4"]);
+//! assert_eq!(explanation.to_string(), "\
+//! mx-4 => margin-left:1rem;margin-right:1rem
+//!   from `mx-{}` at margin.neum:2 with {} = 4
+//!   ml-4 => margin-left:1rem
+//!     from `ml-{}` at margin.neum:3 with {} = 4
+//!   mr-4 => margin-right:1rem
+//!     from `mr-{}` at margin.neum:4 with {} = 4
+//! ");
+//! ```
+
+use crate::ast::Rule;
+use crate::error::ConvertError;
+use std::fmt;
+
+/// How one class was converted, along with every class that was looked up to convert it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub input: String,
+    /// The definition that was used, this is `None` if nothing matched or if the class has
+    /// variants
+    pub rule: Option<Rule>,
+    /// What each placeholder in the name of `rule` captured, in the order they are written
+    pub variables: Vec<(String, String)>,
+    /// The variants the class is wrapped in, the outer most first
+    pub variants: Vec<Rule>,
+    /// The classes looked up with `{{{ }}}` and the base class of a class with variants, in the
+    /// order they were looked up
+    pub lookups: Vec<Explanation>,
+    pub output: Result<Option<String>, Box<ConvertError>>,
+}
+
+impl Explanation {
+    pub fn new<S: AsRef<str>>(input: S) -> Explanation {
+        Explanation {
+            input: input.as_ref().to_string(),
+            rule: None,
+            variables: Vec::new(),
+            variants: Vec::new(),
+            lookups: Vec::new(),
+            output: Ok(None),
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        write!(f, "{indent}{} => ", self.input)?;
+        match &self.output {
+            Ok(Some(x)) => writeln!(f, "{x}")?,
+            Ok(None) => writeln!(f, "not found")?,
+            Err(x) => writeln!(f, "error: {x}")?,
+        }
+        for i in &self.variants {
+            writeln!(f, "{indent}  variant {}", location(i))?;
+        }
+        if let Some(rule) = &self.rule {
+            write!(f, "{indent}  from {}", location(rule))?;
+            if !self.variables.is_empty() {
+                write!(
+                    f,
+                    " with {}",
+                    self.variables
+                        .iter()
+                        .map(|(x, y)| format!("{{{x}}} = {y}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )?;
+            }
+            writeln!(f)?;
+        }
+        for i in &self.lookups {
+            i.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn location(rule: &Rule) -> String {
+    format!(
+        "`{}` at {}:{}",
+        rule.name,
+        rule.file.as_deref().unwrap_or(""),
+        rule.line
+    )
+}
+
+/// An indented tree with a class and what it converted to on each line, followed by the
+/// definitions that were used
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
@@ -7,14 +7,22 @@
 //! let file = "width.neum".to_string(); // Used just for giving errors
 //! let content = std::fs::read_to_string(file.clone()).expect("Cant read file");
 //! let mut neum = Neum::new(&content, Some(&file.to_string())).unwrap();
-//! assert_eq!(neum.convert("w-50%"), Some(String::from("width:50%;")));
+//! assert_eq!(neum.convert("w-50%"), Some(String::from("width:50%")));
 //! ```
 
+pub mod ast;
+pub mod cache;
+pub mod css;
 #[doc(hidden)]
 pub mod error;
+pub mod explain;
 #[doc(hidden)]
 pub mod lexer;
+pub mod list;
 #[doc(hidden)]
 pub mod parse;
+pub mod value;
+
+pub use parse::Matching;
 
 include!("neum.rs");
@@ -0,0 +1,190 @@
+//! Lists of classes picked by name, like the classes that should always be made or the ones that
+//! never should be
+//! ```
+//! # use neum_parse::list::ClassList;
+//! let mut list = ClassList::new();
+//! list.add("bg-{navy,red}").unwrap();
+//! list.add("m-{1..3}").unwrap();
+//! list.add("/text-(sm|lg)/").unwrap();
+//! assert!(list.contains("bg-red"));
+//! assert!(list.contains("m-2"));
+//! assert!(list.contains("text-lg"));
+//! assert!(!list.contains("text-md"));
+//! assert_eq!(list.classes(["text-sm", "text-md"]), vec!["bg-navy", "bg-red", "m-1", "m-2", "m-3", "text-sm"]);
+//! assert!(list.add("/text-(/").is_err());
+//! assert!(list.add("w-{1..100000000}").is_err());
+//! ```
+
+use regex::Regex;
+use std::fmt;
+
+/// The most classes one name in a list can be expanded to
+pub const MAX_CLASSES: usize = 10_000;
+
+/// Why a name could not be added to a [`ClassList`]
+#[derive(Debug, Clone)]
+pub enum ListError {
+    Regex(regex::Error),
+    /// The name expands to more than [`MAX_CLASSES`] classes
+    TooMany(usize),
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ListError::Regex(e) => write!(f, "{e}"),
+            ListError::TooMany(x) => {
+                write!(
+                    f,
+                    "expands to {x} classes, the most that is allowed is {MAX_CLASSES}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ListError {}
+
+impl From<regex::Error> for ListError {
+    fn from(e: regex::Error) -> ListError {
+        ListError::Regex(e)
+    }
+}
+
+/// Classes given by name, `{a,b}` in a name is expanded to each choice and `{1..3}` to each number,
+/// a name written as `/regex/` is every class that the whole regex matches
+#[derive(Debug, Clone, Default)]
+pub struct ClassList {
+    classes: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl ClassList {
+    pub fn new() -> ClassList {
+        ClassList::default()
+    }
+
+    /// Adds a name to the list, this fails if it is a regex that is not valid or it expands to too
+    /// many classes
+    pub fn add<S: AsRef<str>>(&mut self, name: S) -> Result<(), ListError> {
+        let name = name.as_ref();
+        match name.strip_prefix('/').and_then(|x| x.strip_suffix('/')) {
+            Some(regex) => self.patterns.push(Regex::new(&format!("^(?:{regex})$"))?),
+            None => self.classes.append(&mut expand(name)?),
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty() && self.patterns.is_empty()
+    }
+
+    pub fn contains(&self, class: &str) -> bool {
+        self.classes.iter().any(|x| x == class) || self.patterns.iter().any(|x| x.is_match(class))
+    }
+
+    /// Every class in the list, the regexes are only checked against the `known` classes since
+    /// there is no way to list everything they match
+    pub fn classes<'a>(&self, known: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut classes = self.classes.clone();
+        if !self.patterns.is_empty() {
+            let mut matched = known
+                .into_iter()
+                .filter(|x| self.patterns.iter().any(|y| y.is_match(x)))
+                .map(String::from)
+                .collect::<Vec<_>>();
+            matched.sort();
+            classes.append(&mut matched);
+        }
+        classes
+    }
+}
+
+/// Expands each `{a,b}` and `{1..3}` in a name to every class it could be, a range from a larger
+/// number counts down
+/// ```
+/// # use neum_parse::list::expand;
+/// assert_eq!(expand("{m,p}{x,y}-{0..1}").unwrap(), vec!["mx-0", "mx-1", "my-0", "my-1", "px-0", "px-1", "py-0", "py-1"]);
+/// assert_eq!(expand("bg-{red,blue-{100,200}}").unwrap(), vec!["bg-red", "bg-blue-100", "bg-blue-200"]);
+/// assert_eq!(expand("w-{x}").unwrap(), vec!["w-{x}"]);
+/// assert_eq!(expand("w-{3..1}").unwrap(), vec!["w-3", "w-2", "w-1"]);
+/// assert!(expand("w-{1..10000}-{1..2}").is_err());
+/// ```
+pub fn expand(name: &str) -> Result<Vec<String>, ListError> {
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, x) in name.char_indices() {
+        match x {
+            '{' => {
+                if depth == 0 {
+                    start = i;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth > 0 {
+                    continue;
+                }
+                let Some(choices) = choices(&name[start + 1..i])? else {
+                    continue;
+                };
+                let rest = expand(&name[i + 1..])?;
+                let mut classes = Vec::new();
+                for choice in choices {
+                    let choices = expand(&choice)?;
+                    let count = classes.len() + choices.len() * rest.len();
+                    if count > MAX_CLASSES {
+                        return Err(ListError::TooMany(count));
+                    }
+                    for choice in choices {
+                        for rest in &rest {
+                            classes.push(format!("{}{choice}{rest}", &name[..start]));
+                        }
+                    }
+                }
+                return Ok(classes);
+            }
+            _ => {}
+        }
+    }
+    Ok(vec![name.to_string()])
+}
+
+/// The choices in a group, split at the commas that are not in another group or a range of
+/// numbers, `None` if it has neither
+fn choices(group: &str) -> Result<Option<Vec<String>>, ListError> {
+    if let Some((from, to)) = group.split_once("..") {
+        if let (Ok(from), Ok(to)) = (from.parse::<i64>(), to.parse::<i64>()) {
+            let count = from.abs_diff(to).saturating_add(1);
+            if count > MAX_CLASSES as u64 {
+                return Err(ListError::TooMany(
+                    usize::try_from(count).unwrap_or(usize::MAX),
+                ));
+            }
+            return Ok(Some(match from <= to {
+                true => (from..=to).map(|x| x.to_string()).collect(),
+                false => (to..=from).rev().map(|x| x.to_string()).collect(),
+            }));
+        }
+    }
+    let mut choices = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, x) in group.char_indices() {
+        match x {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                choices.push(group[start..i].to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    choices.push(group[start..].to_string());
+    Ok(match choices.len() {
+        1 => None,
+        _ => Some(choices),
+    })
+}
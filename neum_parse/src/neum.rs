@@ -1,16 +1,41 @@
 use std::sync::Arc;
 
-/// This is a Neum converter object
+/// This is a Neum converter object, it can be shared between threads and clones share the classes that have been
+/// converted until one of them is changed
+/// ```
+/// # use neum_parse::*;
+/// let neum = Neum::new("w-{} => width: {}px", None).unwrap();
+/// std::thread::scope(|scope| {
+///     for i in 0..4 {
+///         let neum = &neum;
+///         scope.spawn(move || assert_eq!(neum.convert(format!("w-{i}")), Some(format!("width:{i}px"))));
+///     }
+/// });
+/// assert_eq!(neum.cache.len(), 4);
+///
+/// let mut other = neum.clone();
+/// other.add("h-{} => height: {}px", None).unwrap();
+/// assert_eq!(other.convert("h-1"), Some(String::from("height:1px")));
+/// assert_eq!(neum.cache.len(), 4);
+/// ```
 #[derive(Debug, Clone)]
 pub struct Neum {
     #[doc(hidden)]
-    pub converts: Arc<Vec<(parse::Name, Vec<lexer::Token>)>>,
+    pub converts: Arc<Vec<(parse::Name, ast::Rule)>>,
 
     #[doc(hidden)]
-    pub consts: Arc<hashbrown::HashMap<std::string::String, Vec<lexer::Token>>>,
+    pub consts: Arc<hashbrown::HashMap<std::string::String, ast::Rule>>,
 
     #[doc(hidden)]
-    pub cache: Arc<hashbrown::HashMap<std::string::String, Option<std::string::String>>>,
+    pub variants: Arc<hashbrown::HashMap<std::string::String, ast::Rule>>,
+
+    #[doc(hidden)]
+    pub cache: Arc<cache::Cache>,
+
+    options: parse::Options,
+
+    /// All of the names in `converts` compiled together, it is made the first time it is needed
+    matcher: Arc<std::sync::OnceLock<regex::RegexSet>>,
 }
 
 impl Neum {
@@ -19,46 +44,190 @@ impl Neum {
     /// # use neum_parse::*;
     /// let neum = Neum::new("w-{} => width: {}px", None).unwrap(); // the file is just for error handling
     /// ```
+    /// Every variable used has to be captured by the name of the definition
+    /// ```
+    /// # use neum_parse::*;
+    /// let error = Neum::new("a-{x} => width: {y}", None).unwrap_err();
+    /// assert!(error.to_string().starts_with("Error: UndefinedVariable :1:17"));
+    /// ```
+    /// A placeholder can be given a type like `{:number}` or `{x:length}` so that it only matches valid values, the types are
+    /// `number`, `int`, `length`, `color` and `ident`, more can be defined in a file with `:name => regex` before they are used
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("fs-{:number} => font-size: {*0.25}rem\nfs-{:number}px => font-size: {}px\n:size => sm|lg\ntext-{:size} => font-size: {{{ var-{} }}}\nvar-sm => 12px", None).unwrap();
+    /// assert_eq!(neum.convert("fs-4"), Some(String::from("font-size:1rem")));
+    /// assert_eq!(neum.convert("fs-4px"), Some(String::from("font-size:4px")));
+    /// assert_eq!(neum.convert("fs-abc"), None);
+    /// assert_eq!(neum.convert("text-sm"), Some(String::from("font-size:12px")));
+    /// assert_eq!(neum.convert("text-md"), None);
+    ///
+    /// let error = Neum::new("a-{:size} => width: {}", None).unwrap_err();
+    /// assert!(error.to_string().starts_with("Error: UnknownType :1:4"));
+    /// ```
     pub fn new<S: AsRef<str> + std::fmt::Display>(content: S, file: Option<S>) -> Result<Neum, error::NeumError> {
         let file = file.map(|x| x.as_ref().to_string());
         let output = parse::parse(lexer::lex(file.clone(), content.as_ref().to_string())?, file, content.as_ref().to_string())?;
-        Ok(Neum { converts: Arc::new(output.dynamics.to_vec()), consts: Arc::new(output.statics), cache: Arc::new(hashbrown::HashMap::new()) })
+        Ok(Neum { converts: Arc::new(output.dynamics.to_vec()), consts: Arc::new(output.statics), variants: Arc::new(output.variants), cache: Arc::new(cache::Cache::new()), options: parse::Options::default(), matcher: Arc::new(std::sync::OnceLock::new()) })
     }
 
-    /// Refresh the cache so that if a definition changed it will actually give a different responce
-    /// ```
-    /// # use neum_parse::*;
-    /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
-    /// assert_eq!(neum.convert("mw-5"), None);
-    /// ```
-    /// This will also match the first item it gets
+    /// Clears the cache, adding or combining definitions already drops the cached classes that they could change so this
+    /// is only needed if the definitions were changed some other way
     /// ```
     /// # use neum_parse::*;
     /// let mut neum = Neum::new("w-{}% => width: {}%", None).unwrap();
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%;")));
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%")));
     /// assert_eq!(neum.convert("w-5px"), None);
     ///
     /// neum.add("w-{}px => width: {}px", None).unwrap();
     ///
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%;")));
-    /// assert_eq!(neum.convert("w-5px"), None);
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%")));
+    /// assert_eq!(neum.convert("w-5px"), Some(String::from("width:5px")));
     ///
     /// neum.refresh();
-    ///
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%;")));
-    /// assert_eq!(neum.convert("w-5px"), Some(String::from("width:5px;")));
+    /// assert_eq!(neum.cache_stats().len, 0);
     /// ```
     #[inline(always)]
     pub fn refresh(&mut self) {
-        self.cache = Arc::new(hashbrown::HashMap::new());
+        self.cache = Arc::new(cache::Cache::with_policy(self.cache.policy()));
+    }
+
+    /// Sets how much of what is converted is kept, this also clears the cache
+    /// ```
+    /// # use neum_parse::{*, cache::CachePolicy};
+    /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
+    /// neum.set_cache_policy(CachePolicy::Lru(1));
+    /// for i in ["w-1", "w-1", "w-2", "w-1"] {
+    ///     neum.convert(i);
+    /// }
+    /// let stats = neum.cache_stats();
+    /// assert_eq!((stats.hits, stats.misses, stats.evictions, stats.len), (1, 3, 2, 1));
+    ///
+    /// neum.set_cache_policy(CachePolicy::Disabled);
+    /// neum.convert("w-1");
+    /// neum.convert("w-1");
+    /// assert_eq!(neum.cache_stats().misses, 2);
+    /// ```
+    #[inline(always)]
+    pub fn set_cache_policy(&mut self, policy: cache::CachePolicy) {
+        self.cache = Arc::new(cache::Cache::with_policy(policy));
+    }
+
+    /// How many classes were found in the cache, how many had to be converted and how many were dropped from the cache
+    /// since it was last cleared, looking up another class inside of a definition counts as well
+    #[inline(always)]
+    pub fn cache_stats(&self) -> cache::CacheStats {
+        self.cache.stats()
+    }
+
+    /// Sets how many decimals the numbers worked out by expressions are rounded to, trailing zeros are always removed
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("w-{x}-{y} => width: {x / y * 100}%", None).unwrap();
+    /// assert_eq!(neum.convert("w-1-3"), Some(String::from("width:33.333333%")));
+    /// neum.set_precision(2);
+    /// assert_eq!(neum.convert("w-1-3"), Some(String::from("width:33.33%")));
+    /// assert_eq!(neum.convert("w-1-2"), Some(String::from("width:50%")));
+    /// ```
+    #[inline(always)]
+    pub fn set_precision(&mut self, precision: usize) {
+        self.options.precision = precision;
+        self.refresh();
+    }
+
+    /// Sets how a definition is picked when more than one matches a class, by default the one with the highest priority is used
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("w-{} => width: {}px\nw-{}% => width: {}%", None).unwrap();
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%px")));
+    /// neum.set_matching(Matching::Specific);
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%")));
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
+    /// ```
+    #[inline(always)]
+    pub fn set_matching(&mut self, matching: Matching) {
+        self.options.matching = matching;
+        self.refresh();
+    }
+
+    /// Gives the definitions that match a class which can only be told apart by their priority, this is empty if
+    /// there is only one definition that could be used
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("w-{} => width: {}px\nw-{}% => width: {}%\nw-{x} => width: {x}", None).unwrap();
+    /// assert_eq!(neum.ambiguous("w-5%").len(), 3);
+    /// neum.set_matching(Matching::Specific);
+    /// assert_eq!(neum.ambiguous("w-5%").len(), 0);
+    /// assert_eq!(neum.ambiguous("w-5").iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["w-{}", "w-{x}"]);
+    /// ```
+    pub fn ambiguous<S: AsRef<str>>(&self, input: S) -> Vec<&ast::Rule> {
+        let candidates = parse::candidates(&self.context(), input.as_ref());
+        let ambiguous = match (self.options.matching, candidates.first()) {
+            (parse::Matching::Specific, Some(first)) => candidates.iter().take_while(|x| x.0.specificity == first.0.specificity).collect::<Vec<_>>(),
+            _ => candidates.iter().collect(),
+        };
+        match ambiguous.len() {
+            0 | 1 => Vec::new(),
+            _ => ambiguous.into_iter().map(|x| &x.1).collect(),
+        }
+    }
+
+    /// Gives up to three classes that are close to one that did not match a definition, for catching typos
+    /// ```
+    /// # use neum_parse::*;
+    /// let neum = Neum::new("bg-teal => background-color: teal\nmx-{} => margin-left: {}px\nhover: => &:hover", None).unwrap();
+    /// assert_eq!(neum.suggest("bg-teel"), vec!["bg-teal"]);
+    /// assert_eq!(neum.suggest("mz-4"), vec!["mx-4"]);
+    /// assert_eq!(neum.suggest("hover:bg-tel"), vec!["hover:bg-teal"]);
+    /// assert!(neum.suggest("border").is_empty());
+    /// assert!(neum.cache.is_empty());
+    /// ```
+    pub fn suggest<S: AsRef<str>>(&self, input: S) -> Vec<std::string::String> {
+        parse::suggestions(&self.context(), input.as_ref())
+    }
+
+    /// Every class in a list, with its regexes checked against the classes that are defined without placeholders
+    /// ```
+    /// # use neum_parse::{*, list::ClassList};
+    /// let neum = Neum::new("bg-red => background-color: red\nbg-blue => background-color: blue\nw-{} => width: {}px", None).unwrap();
+    /// let mut safelist = ClassList::new();
+    /// safelist.add("/bg-.*/").unwrap();
+    /// safelist.add("w-{1,2}").unwrap();
+    /// assert_eq!(neum.select(&safelist), vec!["w-1", "w-2", "bg-blue", "bg-red"]);
+    /// ```
+    pub fn select(&self, list: &list::ClassList) -> Vec<std::string::String> {
+        list.classes(self.consts.keys().map(|x| x.as_str()))
+    }
+
+    /// The definitions a class would be converted with, the variants it is wrapped in with the outer most first and the
+    /// definition of the class without them, nothing is converted so the classes it looks up are not included
+    /// ```
+    /// # use neum_parse::*;
+    /// let neum = Neum::new("w-{} => width: {}px\nhover: => &:hover\nmd: => @media (min-width: 768px)", None).unwrap();
+    /// let (variants, rule) = neum.rules("md:hover:w-5").unwrap();
+    /// assert_eq!(variants.iter().map(|x| x.line).collect::<Vec<_>>(), vec![3, 2]);
+    /// assert_eq!(rule.name, "w-{}");
+    /// assert!(neum.rules("h-5").is_none());
+    /// assert!(neum.rules("md:hover:md:w-5").is_none());
+    /// ```
+    pub fn rules<S: AsRef<str>>(&self, input: S) -> Option<(Vec<&ast::Rule>, &ast::Rule)> {
+        let input = input.as_ref();
+        let (variants, base) = match parse::split_variants(&self.variants, input) {
+            Some((prefixes, base)) => (prefixes.iter().map(|x| &self.variants[*x]).collect(), base),
+            None => (Vec::new(), input),
+        };
+        let rule = match self.consts.get(base) {
+            Some(rule) => rule,
+            None => &parse::candidates(&self.context(), base).first()?.1,
+        };
+        Some((variants, rule))
     }
 
-    /// Takes your current Neum object and finds your input and gives the output
+    /// Takes your current Neum object and finds your input and gives the output, the css is always minified, use
+    /// [`style`](Neum::style) and [`css::render`] to write it out another way
     /// ```
     /// # use neum_parse::*;
     /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
     /// assert_eq!(neum.convert("mw-5"), None);
     /// ```
     /// This will also match the first item it gets
@@ -66,31 +235,171 @@ impl Neum {
     /// # use neum_parse::*;
     /// let mut neum = Neum::new("w-{}% => width: {}%", None).unwrap();
     /// neum.add("w-{} => width: {}px", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%;")));
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%")));
     ///
     /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
     /// neum.add("w-{}% => width: {}%", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%px;")));
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%px")));
+    /// ```
+    /// Arithmetic keeps the unit of what was captured, a unit right after the `}` is only used when the result does not have one
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("m-{} => margin: {*0.25}rem\nw-{x}-{y} => width: {x + y}", None).unwrap();
+    /// assert_eq!(neum.convert("m-4"), Some(String::from("margin:1rem")));
+    /// assert_eq!(neum.convert("m-4px"), Some(String::from("margin:1px")));
+    /// assert_eq!(neum.convert("w-4px-1rem"), Some(String::from("width:calc(4px + 1rem)")));
+    /// assert_eq!(neum.convert("w-4px-1deg"), None);
+    /// ```
+    /// Definitions ending in `:` are variants, a class prefixed by them is wrapped in the variant's
+    /// selector (`&` being the class) or at-rule
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("w-{} => width: {}px\nvar-md => 768px\nhover: => &:hover\nmd: => @media (min-width: {{{ var-md }}})", None).unwrap();
+    /// assert_eq!(neum.convert("hover:w-5"), Some(String::from(r".hover\:w-5:hover{width:5px}")));
+    /// assert_eq!(neum.convert("md:hover:w-5"), Some(String::from(r"@media (min-width:768px){.md\:hover\:w-5:hover{width:5px}}")));
+    /// assert_eq!(neum.convert("lg:w-5"), None);
+    /// assert_eq!(neum.convert("md:md:w-5"), None);
     /// ```
     #[inline(always)]
-    pub fn convert<S: AsRef<str>>(&mut self, input: S) -> Option<std::string::String> {
-        parse::converts(self.converts.clone(), self.consts.clone(), Arc::make_mut(&mut self.cache), input.as_ref())
+    pub fn convert<S: AsRef<str>>(&self, input: S) -> Option<std::string::String> {
+        self.try_convert(input).ok().flatten()
+    }
+
+    /// The same as [convert](Neum::convert) but gives an error when a definition matched but could not be converted
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("m-{} => margin: {*0.25}rem\nmx-{} => {{{ ml-{} }}}{{{ mr-{} }}}\nml-{} => margin-left: {*0.25}rem", Some("margin.neum")).unwrap();
+    /// assert_eq!(neum.try_convert("m-4"), Ok(Some(String::from("margin:1rem"))));
+    /// assert_eq!(neum.try_convert("w-4"), Ok(None));
+    ///
+    /// let error = neum.try_convert("m-abc").unwrap_err();
+    /// assert_eq!(error.kind, error::ConvertErrorKind::NotANumber(String::from("abc")));
+    /// assert_eq!(error.to_string(), "`m-abc`: rule `m-{}` at margin.neum:1 needs a number, got `abc`");
+    ///
+    /// let error = neum.try_convert("mx-abc").unwrap_err();
+    /// assert_eq!(error.lookup, Some(String::from("ml-abc")));
+    /// assert_eq!(error.to_string(), "`mx-abc`: rule `ml-{}` at margin.neum:3 (looking up `ml-abc`) needs a number, got `abc`");
+    ///
+    ///
+    /// // `mr-4` is not defined so `mx-4` is not a class
+    /// assert_eq!(neum.try_convert("mx-4"), Ok(None));
+    /// ```
+    #[inline(always)]
+    pub fn try_convert<S: AsRef<str>>(&self, input: S) -> Result<Option<std::string::String>, Box<error::ConvertError>> {
+        Ok(parse::converts(&self.context(), &self.cache, input.as_ref())?.map(|x| css::render(&x, css::Format::Minify)))
+    }
+
+    /// The css of a class as a rule for it, inside the at-rules of its variants, which [`css::render`] writes out, each
+    /// declaration has the file and line of the definition that wrote it
+    /// ```
+    /// # use neum_parse::*;
+    /// # use neum_parse::css::{Block, Format, Origin};
+    /// let neum = Neum::new("w-{} => width: {}px; height: 1px\nvar-md => 768px\nhover: => &:hover\nmd: => @media (min-width: {{{ var-md }}})", None).unwrap();
+    /// let origin = Some(Origin { file: None, line: 1 });
+    /// let block = neum.style("md:hover:w-5").unwrap().unwrap();
+    /// assert_eq!(block, Block::AtRule(
+    ///     String::from("@media (min-width: 768px)"),
+    ///     vec![Block::Rule(
+    ///         String::from(r".md\:hover\:w-5:hover"),
+    ///         vec![Block::Declaration(String::from("width: 5px"), origin.clone()), Block::Declaration(String::from("height: 1px"), origin.clone())]
+    ///     )]
+    /// ));
+    /// assert_eq!(css::render(&[block], Format::Minify), r"@media (min-width:768px){.md\:hover\:w-5:hover{width:5px;height:1px}}");
+    /// assert_eq!(neum.style("h-5"), Ok(None));
+    ///
+    /// let neum = Neum::new("mx-{} => {{{ ml-{} }}}; {{{ mr-{} }}}\nml-{} => margin-left: {}px\nmr-{} => margin-right: {}px", Some("margin.neum")).unwrap();
+    /// let Some(Block::Rule(_, declarations)) = neum.style("mx-4").unwrap() else { panic!() };
+    /// let lines = declarations.iter().map(|x| match x {
+    ///     Block::Declaration(_, Some(x)) => x.line,
+    ///     _ => 0,
+    /// });
+    /// assert_eq!(lines.collect::<Vec<_>>(), vec![2, 3]);
+    /// ```
+    /// Only a `;` written in a definition ends a declaration, what a variable captured or a lookup gives is used as it is
+    /// ```
+    /// # use neum_parse::*;
+    /// # use neum_parse::css::Block;
+    /// let neum = Neum::new("half => .5\no-half => opacity: {{{ half }}}\ncontent-{} => content: \"{}\"\nimp-{} => {{{ {} }}} !important", None).unwrap();
+    /// let declarations = |x| match neum.style(x).unwrap() {
+    ///     Some(Block::Rule(_, blocks)) => blocks.into_iter().map(|x| match x {
+    ///         Block::Declaration(x, _) => x,
+    ///         _ => String::new(),
+    ///     }).collect::<Vec<_>>(),
+    ///     _ => Vec::new(),
+    /// };
+    /// assert_eq!(declarations("o-half"), vec!["opacity: .5"]);
+    /// assert_eq!(declarations("content-a;b{c}"), vec!["content: \"a;b{c}\""]);
+    /// assert_eq!(declarations("imp-o-half"), vec!["opacity: .5 !important"]);
+    /// ```
+    pub fn style<S: AsRef<str>>(&self, input: S) -> Result<Option<css::Block>, Box<error::ConvertError>> {
+        parse::style(&self.context(), &self.cache, input.as_ref())
     }
 
-    /// Add some more Neum definitions to your Neum object, this will also add your item to the lowest priority
+    /// Converts a class without the cache and gives the definitions that were used for it and for every class it looked
+    /// up, along with the file and line each one is from
+    /// ```
+    /// # use neum_parse::*;
+    /// let neum = Neum::new("w-{} => width: {}px\nvar-md => 768px\nmd: => @media (min-width: {{{ var-md }}})", Some("style.neum")).unwrap();
+    /// let explanation = neum.explain("md:w-5");
+    /// assert_eq!(explanation.output, Ok(Some(String::from(r"@media (min-width:768px){.md\:w-5{width:5px}}"))));
+    /// assert_eq!(explanation.variants[0].line, 3);
+    /// assert_eq!(explanation.lookups[0].rule.as_ref().map(|x| x.name.as_str()), Some("w-{}"));
+    /// assert_eq!(explanation.lookups[1].input, "var-md");
+    ///
+    /// assert_eq!(neum.explain("h-5").output, Ok(None));
+    /// assert!(neum.cache.is_empty());
+    /// ```
+    pub fn explain<S: AsRef<str>>(&self, input: S) -> explain::Explanation {
+        let trace = std::cell::RefCell::new(Vec::new());
+        let context = parse::Context { trace: Some(&trace), ..self.context() };
+        let _ = parse::converts(&context, &cache::Cache::with_policy(cache::CachePolicy::Disabled), input.as_ref());
+        trace.into_inner().pop().expect("Internal error, nothing was traced")
+    }
+
+    #[inline(always)]
+    fn context(&self) -> parse::Context<'_> {
+        parse::Context { parsed: &self.converts, matcher: self.matcher.get_or_init(|| parse::matcher(&self.converts)), consts: &self.consts, variants: &self.variants, options: self.options, trace: None }
+    }
+
+    /// Drops the cached classes that adding the definitions in `neum` could change, along with the ones that looked them
+    /// up, this has to be done before they are added
+    #[inline(always)]
+    fn drop_affected(&mut self, neum: &Neum, priority: bool) {
+        if self.cache.is_empty() {
+            return;
+        }
+        Arc::make_mut(&mut self.cache);
+        let old = self.context();
+        let new = parse::Context { options: self.options, ..neum.context() };
+        self.cache.remove_where(|input, output| parse::affected(&old, &new, priority, input, output));
+    }
+
+    /// Makes the matcher be compiled again the next time it is needed and stops sharing the cache with clones, this has
+    /// to be done whenever the definitions change
+    #[inline(always)]
+    fn invalidate(&mut self) {
+        self.matcher = Arc::new(std::sync::OnceLock::new());
+        Arc::make_mut(&mut self.cache);
+    }
+
+    /// Add some more Neum definitions to your Neum object, this will also add your item to the lowest priority, though
+    /// statics and variants that are already defined are replaced by the new ones
     /// ```
     /// # use neum_parse::*;
     /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
     /// assert_eq!(neum.convert("mw-5"), None);
     ///
-    /// neum.add("mw-{} => max-width: {}px", None).unwrap();
-    /// neum.refresh();
+    /// neum.add("mw-{} => max-width: {}px\nw-5 => width: 1px", None).unwrap();
+    ///
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:1px")));
+    /// assert_eq!(neum.convert("mw-5"), Some(String::from("max-width:5px")));
     ///
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
-    /// assert_eq!(neum.convert("mw-5"), Some(String::from("max-width:5px;")));
+    /// neum.add("w-5 => width: 2px\nw-{} => width: {}rem", None).unwrap();
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:2px")));
+    /// assert_eq!(neum.convert("w-6"), Some(String::from("width:6px")));
     /// ```
     #[inline(always)]
     pub fn add<S: AsRef<str> + std::fmt::Display>(
@@ -99,8 +408,9 @@ impl Neum {
         file: Option<S>,
     ) -> Result<(), error::NeumError> {
         let mut neum = Neum::new(content, file)?;
-        Arc::make_mut(&mut self.converts).append(Arc::make_mut(&mut neum.converts));
-        Arc::make_mut(&mut self.consts).extend((*neum.consts).clone());
+        Arc::make_mut(&mut self.consts).retain(|x, _| !neum.consts.contains_key(x));
+        Arc::make_mut(&mut self.variants).retain(|x, _| !neum.variants.contains_key(x));
+        self.combine(&mut neum);
         Ok(())
     }
     
@@ -109,13 +419,15 @@ impl Neum {
     /// # use neum_parse::*;
     /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
     /// neum.add("w-{}% => width: {}%", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%px;")));
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%px")));
     ///
-    /// let mut neum = Neum::new("w-{} => width: {}px", None).unwrap();
-    /// neum.add_priority("w-{}% => width: {}%", None).unwrap();
-    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px;")));
-    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%;")));
+    /// let mut neum = Neum::new("w-{} => width: {}px\nm-{} => {{{ w-{} }}}", None).unwrap();
+    /// assert_eq!(neum.convert("m-5%"), Some(String::from("width:5%px")));
+    /// neum.add_priority("w-{}% => width: {}%\nw-5 => width: 1px", None).unwrap();
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:1px")));
+    /// assert_eq!(neum.convert("w-5%"), Some(String::from("width:5%")));
+    /// assert_eq!(neum.convert("m-5%"), Some(String::from("width:5%")));
     /// ```
     #[inline(always)]
     pub fn add_priority<S: AsRef<str> + std::fmt::Display>(
@@ -123,21 +435,40 @@ impl Neum {
         content: S,
         file: Option<S>,
     ) -> Result<(), error::NeumError> {
-        let mut neum = Neum::new(content, file)?;
-        Arc::make_mut(&mut neum.converts).append(Arc::make_mut(&mut self.converts));
-        self.converts = neum.converts;
-        Arc::make_mut(&mut neum.consts).extend((*self.consts).clone());
-        self.consts = neum.consts;
+        self.combine_priority(&mut Neum::new(content, file)?);
         Ok(())
     }
 
+    /// Keeps only the definitions that `keep` gives true for, like the ones from some files
+    /// ```
+    /// # use neum_parse::*;
+    /// let mut neum = Neum::new("w-{} => width: {}px\ncolor => red", Some("width.neum")).unwrap();
+    /// neum.add("h-{} => height: {}px", Some("height.neum")).unwrap();
+    /// assert_eq!(neum.convert("h-5"), Some(String::from("height:5px")));
+    ///
+    /// neum.retain(|x| x.file.as_deref() == Some("width.neum"));
+    /// assert_eq!(neum.convert("h-5"), None);
+    /// assert_eq!(neum.convert("w-5"), Some(String::from("width:5px")));
+    /// assert_eq!(neum.convert("color"), Some(String::from("red")));
+    /// ```
+    pub fn retain<F: Fn(&ast::Rule) -> bool>(&mut self, keep: F) {
+        Arc::make_mut(&mut self.converts).retain(|x| keep(&x.1));
+        Arc::make_mut(&mut self.consts).retain(|_, x| keep(x));
+        Arc::make_mut(&mut self.variants).retain(|_, x| keep(x));
+        self.invalidate();
+        self.refresh();
+    }
+
     /// Returns a empty Neum type with nothing defined
     #[inline(always)]
     pub fn empty() -> Neum {
         Neum {
             converts: Arc::new(Vec::new()),
             consts: Arc::new(hashbrown::HashMap::new()),
-            cache: Arc::new(hashbrown::HashMap::new())
+            variants: Arc::new(hashbrown::HashMap::new()),
+            cache: Arc::new(cache::Cache::new()),
+            options: parse::Options::default(),
+            matcher: Arc::new(std::sync::OnceLock::new()),
         }
     }
 
@@ -153,17 +484,22 @@ impl Neum {
     /// neum.combine(&mut file_one);
     /// neum.combine(&mut file_two);
     ///
-    /// assert_eq!(neum.convert("color"), Some(String::from("red;")));
-    /// assert_eq!(neum.convert("hello-world"), Some(String::from("hello world;")));
+    /// assert_eq!(neum.convert("color"), Some(String::from("red")));
+    /// assert_eq!(neum.convert("hello-world"), Some(String::from("hello world")));
     /// ```
     #[inline(always)]
     pub fn combine(
         &mut self,
         neum: &mut Neum,
     ) {
+        self.drop_affected(neum, false);
         Arc::make_mut(&mut self.converts).append(Arc::make_mut(&mut neum.converts));
         Arc::make_mut(&mut neum.consts).extend(Arc::make_mut(&mut self.consts).clone());
         self.consts = neum.consts.clone();
+        Arc::make_mut(&mut neum.variants).extend(Arc::make_mut(&mut self.variants).clone());
+        self.variants = neum.variants.clone();
+        self.invalidate();
+        neum.invalidate();
     }
 
     /// Combine two Neum items, the first item has priority over the others
@@ -178,16 +514,20 @@ impl Neum {
     /// neum.combine(&mut file_one);
     /// neum.combine_priority(&mut file_two);
     ///
-    /// assert_eq!(neum.convert("color"), Some(String::from("yellow;")));
-    /// assert_eq!(neum.convert("hello-world"), Some(String::from("goodbye world;")));
+    /// assert_eq!(neum.convert("color"), Some(String::from("yellow")));
+    /// assert_eq!(neum.convert("hello-world"), Some(String::from("goodbye world")));
     /// ```
     #[inline(always)]
     pub fn combine_priority(
         &mut self,
         neum: &mut Neum,
     ) {
+        self.drop_affected(neum, true);
         Arc::make_mut(&mut neum.converts).append(Arc::make_mut(&mut self.converts));
         Arc::make_mut(&mut self.consts).extend(Arc::make_mut(&mut neum.consts).clone());
+        Arc::make_mut(&mut self.variants).extend(Arc::make_mut(&mut neum.variants).clone());
         self.converts = neum.converts.clone();
+        self.invalidate();
+        neum.invalidate();
     }
 }
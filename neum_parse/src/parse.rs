@@ -1,8 +1,14 @@
-use crate::error::{ErrorType, NeumError};
+use crate::ast::{self, Node, NodeKind, Rule};
+use crate::cache::Cache;
+use crate::css::{self, Block, Origin};
+use crate::error::{get_loc, ConvertError, ErrorType, NeumError};
+use crate::explain::Explanation;
 use crate::lexer::Token;
-use core::slice::Iter;
+use crate::value::{self, Value};
 use hashbrown::HashMap;
-use regex::Regex;
+use regex::{Regex, RegexSet};
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
 use std::ops::Range;
 use std::sync::Arc;
 
@@ -11,13 +17,74 @@ use std::sync::Arc;
 pub struct Name {
     pub regex: Arc<Regex>,
     pub variables: Arc<Vec<String>>,
+    pub specificity: Specificity,
+}
+
+/// How a definition is picked when more than one definition matches a class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Matching {
+    /// The definition with the highest priority is used
+    #[default]
+    First,
+    /// The most specific definition is used, see [`Specificity`] for how they are compared,
+    /// definitions that are just as specific as each other fall back to priority
+    Specific,
+}
+
+/// Settings that change how classes are converted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    /// How many decimals numbers from expressions are rounded to
+    pub precision: usize,
+    pub matching: Matching,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            precision: value::DEFAULT_PRECISION,
+            matching: Matching::First,
+        }
+    }
+}
+
+/// How specific the name of a definition is, a name with more literal characters is more
+/// specific, then the one with fewer placeholders, then the one with more typed placeholders
+/// ```
+/// # use neum_parse::parse::Specificity;
+/// let percent = Specificity { literals: 3, captures: 1, typed: 0 }; // w-{}%
+/// let any = Specificity { literals: 2, captures: 1, typed: 0 }; // w-{}
+/// let number = Specificity { literals: 2, captures: 1, typed: 1 }; // w-{:number}
+/// assert!(percent > number && number > any);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Specificity {
+    pub literals: usize,
+    pub captures: usize,
+    pub typed: usize,
+}
+
+impl Ord for Specificity {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.literals
+            .cmp(&other.literals)
+            .then(other.captures.cmp(&self.captures))
+            .then(self.typed.cmp(&other.typed))
+    }
+}
+
+impl PartialOrd for Specificity {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct Parse {
-    pub dynamics: Vec<(Name, Vec<Token>)>,
-    pub statics: HashMap<String, Vec<Token>>,
+    pub dynamics: Vec<(Name, Rule)>,
+    pub statics: HashMap<String, Rule>,
+    pub variants: HashMap<String, Rule>,
 }
 
 #[inline(always)]
@@ -30,6 +97,8 @@ pub fn parse<S: AsRef<str>>(
     let file = file.map(|x| x.as_ref().to_string());
     let mut list = Vec::new();
     let mut consts = HashMap::new();
+    let mut variants = HashMap::new();
+    let mut types = HashMap::new();
     let mut token = tokens.iter();
     while let Some(next) = token.next() {
         match next.0 {
@@ -44,12 +113,15 @@ pub fn parse<S: AsRef<str>>(
                         break;
                     }
                 }
+                let pattern = content[next.1.start..last.1.start].trim().to_string();
 
                 let mut variables: Vec<String> = Vec::new();
                 let mut regex = "^".to_string();
                 let mut text = String::new();
                 let mut name_iter = name.iter();
                 let mut is_const = true;
+                let mut literals = 0;
+                let mut typed = 0;
                 while let Some(i) = name_iter.next() {
                     let value = match &i.0 {
                         Token::ReplacementStart => {
@@ -65,8 +137,10 @@ pub fn parse<S: AsRef<str>>(
                                     )
                                 })?
                                 .clone();
+                            let mut capture = "(.*)".to_string();
                             if let Token::String(x) = &next.0 {
-                                if variables.contains(x) {
+                                let (x, kind) = x.split_once(':').unwrap_or((x, ""));
+                                if variables.iter().any(|y| y == x) {
                                     return Err(NeumError::new(
                                         ErrorType::VariableMultiDefine,
                                         file,
@@ -74,6 +148,20 @@ pub fn parse<S: AsRef<str>>(
                                         next.1,
                                     ));
                                 }
+                                if !kind.is_empty() {
+                                    typed += 1;
+                                    capture = format!(
+                                        "({})",
+                                        capture_type(kind, &types).ok_or_else(|| {
+                                            NeumError::new(
+                                                ErrorType::UnknownType,
+                                                file.clone(),
+                                                content.clone(),
+                                                next.1.end - kind.len()..next.1.end,
+                                            )
+                                        })?
+                                    );
+                                }
                                 variables.push(x.to_string());
                                 let next_name = name_iter.next().ok_or_else(|| {
                                     NeumError::new(
@@ -110,7 +198,7 @@ pub fn parse<S: AsRef<str>>(
                                 ));
                             }
 
-                            Ok("(.*)".to_string())
+                            Ok(capture)
                         }
                         Token::Add => Ok("+".to_string()),
                         Token::Subtract => Ok(r"-".to_string()),
@@ -129,6 +217,9 @@ pub fn parse<S: AsRef<str>>(
                     match value {
                         Ok(x) => {
                             text.push_str(x.as_str());
+                            if !matches!(i.0, Token::ReplacementStart) {
+                                literals += x.len();
+                            }
                             if matches!(
                                 i.0,
                                 Token::Subtract
@@ -147,33 +238,27 @@ pub fn parse<S: AsRef<str>>(
 
                 regex.push('$');
 
-                let mut first = &token
-                    .next()
-                    .ok_or_else(|| {
+                let mut first = token.next().ok_or_else(|| {
+                    NeumError::new(
+                        ErrorType::UnexpectedEndOfFile,
+                        file.clone(),
+                        content.clone(),
+                        last.1.end..last.1.end + 1,
+                    )
+                })?;
+                if first.0 == Token::Space {
+                    first = token.next().ok_or_else(|| {
                         NeumError::new(
                             ErrorType::UnexpectedEndOfFile,
                             file.clone(),
                             content.clone(),
                             last.1.end..last.1.end + 1,
                         )
-                    })?
-                    .0;
-                if first == &Token::Space {
-                    first = &token
-                        .next()
-                        .ok_or_else(|| {
-                            NeumError::new(
-                                ErrorType::UnexpectedEndOfFile,
-                                file.clone(),
-                                content.clone(),
-                                last.1.end..last.1.end + 1,
-                            )
-                        })?
-                        .0;
+                    })?;
                 }
                 let mut convert_to = Vec::new();
                 let mut multiequal_count = 0;
-                let go_to = match first {
+                let go_to = match first.0 {
                     Token::MultiEqualStart => {
                         multiequal_count += 1;
                         Token::MultiEqualEnd
@@ -196,32 +281,7 @@ pub fn parse<S: AsRef<str>>(
                             && i.0 == Token::MultiEqualEnd
                             && multiequal_count != 0))
                     {
-                        if !matches!(
-                            i.0,
-                            Token::ReplacementStart
-                                | Token::ReplacementEnd
-                                | Token::Add
-                                | Token::Subtract
-                                | Token::Times
-                                | Token::Divide
-                                | Token::Number(_)
-                                | Token::String(_)
-                                | Token::SemiColon
-                                | Token::NewLine
-                                | Token::FullReplacementStart
-                                | Token::FullReplacementEnd
-                                | Token::Space
-                                | Token::MultiEqualStart
-                                | Token::MultiEqualEnd
-                        ) {
-                            return Err(NeumError::new(
-                                ErrorType::UnexpectedToken,
-                                file,
-                                content.clone(),
-                                i.clone().1,
-                            ));
-                        }
-                        convert_to.push(i.0.clone());
+                        convert_to.push(i.clone());
                     } else {
                         broke = true;
                         break;
@@ -235,7 +295,27 @@ pub fn parse<S: AsRef<str>>(
                         last.1.end..last.1.end + 1,
                     ));
                 }
-                if is_const {
+                if is_const && text.len() > 1 && text.starts_with(':') && !text.ends_with(':') {
+                    let span = convert_to.first().map_or(last.1.start, |x| x.1.start)
+                        ..convert_to.last().map_or(last.1.start, |x| x.1.end);
+                    let regex = content[span.clone()].trim();
+                    if !Regex::new(regex).is_ok_and(|x| x.captures_len() == 1) {
+                        return Err(NeumError::new(ErrorType::InvalidRegex, file, content, span));
+                    }
+                    types.insert(text[1..].to_string(), format!("(?:{regex})"));
+                    continue;
+                }
+                let convert_to = Rule {
+                    name: pattern,
+                    body: ast::body(&convert_to, &variables, &file, &content)?,
+                    span: next.1.start..last.1.end,
+                    file: file.clone(),
+                    line: get_loc(&content, next.1.start).map_or(0, |x| x.1),
+                };
+                if is_const && text.len() > 1 && text.ends_with(':') {
+                    text.pop();
+                    variants.insert(text, convert_to);
+                } else if is_const {
                     consts.insert(text, convert_to);
                 } else {
                     list.push((
@@ -244,6 +324,11 @@ pub fn parse<S: AsRef<str>>(
                                 Regex::new(&regex)
                                     .expect("Internal error, could not make regex from input"),
                             ),
+                            specificity: Specificity {
+                                literals,
+                                captures: variables.len(),
+                                typed,
+                            },
                             variables: Arc::new(variables),
                         },
                         convert_to,
@@ -263,294 +348,607 @@ pub fn parse<S: AsRef<str>>(
     Ok(Parse {
         dynamics: list,
         statics: consts,
+        variants,
+    })
+}
+
+/// Gives the regex that a typed placeholder like `{:number}` matches, looking at the types defined
+/// in the file before the built in ones
+fn capture_type(kind: &str, types: &HashMap<String, String>) -> Option<String> {
+    const NUMBER: &str = r"-?(?:[0-9]+(?:\.[0-9]*)?|\.[0-9]+)";
+    if let Some(x) = types.get(kind) {
+        return Some(x.clone());
+    }
+    Some(match kind {
+        "number" => NUMBER.to_string(),
+        "int" => "-?[0-9]+".to_string(),
+        "length" => format!(
+            "{NUMBER}(?i:{})?",
+            value::LENGTHS
+                .iter()
+                .map(|x| regex::escape(x))
+                .collect::<Vec<String>>()
+                .join("|")
+        ),
+        "color" => concat!(
+            "#(?:[0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})",
+            r"|(?:rgba?|hsla?|hwb|lab|lch|oklab|oklch|color)\([^()]*\)",
+            "|transparent|currentcolor"
+        )
+        .to_string(),
+        "ident" => "-?[a-zA-Z_][a-zA-Z0-9_-]*".to_string(),
+        _ => return None,
     })
 }
 
+/// The definitions and settings that classes are converted with
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    pub parsed: &'a [(Name, Rule)],
+    /// The names of `parsed` compiled with [`matcher`]
+    pub matcher: &'a RegexSet,
+    pub consts: &'a HashMap<String, Rule>,
+    pub variants: &'a HashMap<String, Rule>,
+    pub options: Options,
+    /// Where to record how each class is converted, the class being converted is on top with
+    /// the ones it is looked up by under it
+    pub trace: Option<&'a RefCell<Vec<Explanation>>>,
+}
+
+/// Compiles the names of all dynamic definitions into one set so that a class is checked against
+/// all of them in one pass
+#[inline(always)]
+pub fn matcher(parsed: &[(Name, Rule)]) -> RegexSet {
+    RegexSet::new(parsed.iter().map(|x| x.0.regex.as_str()))
+        .expect("Internal error, could not make regex set from definitions")
+}
+
+/// Gives every dynamic definition that matches a class in the order they would be used in
+#[inline(always)]
+pub fn candidates<'a>(context: &Context<'a>, input: &str) -> Vec<&'a (Name, Rule)> {
+    let parsed = context.parsed;
+    let mut matches = context
+        .matcher
+        .matches(input)
+        .into_iter()
+        .map(|x| &parsed[x])
+        .collect::<Vec<_>>();
+    if context.options.matching == Matching::Specific {
+        // a stable sort so that definitions that are just as specific stay in priority order
+        matches.sort_by_key(|x| Reverse(x.0.specificity));
+    }
+    matches
+}
+
+/// If adding the definitions in `new` to the ones in `old` could change what a cached class is
+/// converted to, `priority` being if the new definitions are used before the old ones, classes
+/// that looked up a class that is changed are not checked here
+pub fn affected(
+    old: &Context,
+    new: &Context,
+    priority: bool,
+    input: &str,
+    output: Option<&[Block]>,
+) -> bool {
+    // a class that was not found may be found now
+    if output.is_none() {
+        return true;
+    }
+    let wins = |new: bool, old: bool| new && (priority || !old);
+    if wins(
+        new.consts.contains_key(input),
+        old.consts.contains_key(input),
+    ) {
+        return true;
+    }
+    if old.consts.contains_key(input) {
+        return false;
+    }
+    let mut prefixes = input.split(':');
+    prefixes.next_back();
+    if prefixes.any(|x| wins(new.variants.contains_key(x), old.variants.contains_key(x))) {
+        return true;
+    }
+    if split_variants(old.variants, input).is_some() {
+        return false;
+    }
+    match (
+        candidates(new, input).first(),
+        candidates(old, input).first(),
+    ) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(x), Some(y)) => match old.options.matching {
+            Matching::First => priority,
+            Matching::Specific => match x.0.specificity.cmp(&y.0.specificity) {
+                Ordering::Greater => true,
+                Ordering::Equal => priority,
+                Ordering::Less => false,
+            },
+        },
+    }
+}
+
+/// Gives classes that are close to one that did not match a definition, the statics that are a few
+/// edits away and the class with its start swapped for the start of a dynamic definition if that
+/// can be converted, the closest ones first, the classes that are tried are converted with a cache
+/// of their own so that they do not fill the one that is used for real classes
+pub fn suggestions(context: &Context, input: &str) -> Vec<String> {
+    suggest(context, &Cache::new(), input)
+}
+
+fn suggest(context: &Context, cache: &Cache, input: &str) -> Vec<String> {
+    if let Some((_, base)) = split_variants(context.variants, input) {
+        let prefixes = &input[..input.len() - base.len()];
+        return suggest(context, cache, base)
+            .into_iter()
+            .map(|x| format!("{prefixes}{x}"))
+            .collect();
+    }
+    let close = |distance: usize, len: usize| distance > 0 && distance <= 2 && distance * 2 < len;
+    let mut found = Vec::new();
+    for name in context.consts.keys() {
+        let distance = distance(input, name);
+        if close(distance, name.len()) {
+            found.push((distance, name.clone()));
+        }
+    }
+    for (_, rule) in context.parsed {
+        let literal = rule.name.split('{').next().unwrap_or_default();
+        if !literal.ends_with('-') || input.starts_with(literal) {
+            continue;
+        }
+        // the start of the class up to as many `-` as the definition starts with
+        let Some((end, _)) = input
+            .match_indices('-')
+            .nth(literal.matches('-').count() - 1)
+        else {
+            continue;
+        };
+        let distance = distance(&input[..=end], literal);
+        let class = format!("{literal}{}", &input[end + 1..]);
+        if close(distance, literal.len())
+            && converts(context, cache, &class).is_ok_and(|x| x.is_some())
+        {
+            found.push((distance, class));
+        }
+    }
+    found.sort();
+    found.dedup_by(|x, y| x.1 == y.1);
+    found.into_iter().take(3).map(|x| x.1).collect()
+}
+
+/// How many characters have to be added, removed or changed to turn one string into the other
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = (previous + usize::from(x != *y))
+                .min(row[j] + 1)
+                .min(current + 1);
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
 #[inline(always)]
 pub fn converts<S: AsRef<str> + std::fmt::Display>(
-    parsed: Arc<Vec<(Name, Vec<Token>)>>,
-    consts: Arc<HashMap<String, Vec<Token>>>,
-    cache: &mut HashMap<String, Option<String>>,
+    context: &Context,
+    cache: &Cache,
     input: S,
-) -> Option<String> {
+) -> Result<Option<Vec<Block>>, Box<ConvertError>> {
     let input = input.as_ref();
+    let Some(trace) = context.trace else {
+        return convert(context, cache, input);
+    };
+    trace.borrow_mut().push(Explanation::new(input));
+    let output = convert(context, cache, input);
+    let mut trace = trace.borrow_mut();
+    let mut explanation = trace
+        .pop()
+        .expect("Internal error, the trace was changed while converting");
+    explanation.output = match &output {
+        Ok(x) => Ok(x.as_ref().map(|x| css::render(x, css::Format::Minify))),
+        Err(e) => Err(e.clone()),
+    };
+    match trace.last_mut() {
+        Some(parent) => parent.lookups.push(explanation),
+        // the class that was asked for is left in the trace
+        None => trace.push(explanation),
+    }
+    output
+}
+
+/// Records something about the class that is being converted if it is being traced
+#[inline(always)]
+fn record(context: &Context, f: impl FnOnce(&mut Explanation)) {
+    if let Some(trace) = context.trace {
+        if let Some(explanation) = trace.borrow_mut().last_mut() {
+            f(explanation);
+        }
+    }
+}
+
+#[inline(always)]
+fn convert(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+) -> Result<Option<Vec<Block>>, Box<ConvertError>> {
     if let Some(item) = cache.get(input) {
-        return item.clone();
+        return Ok(item);
+    }
+
+    let mut lookups = Vec::new();
+    if let Some((prefixes, base)) = split_variants(context.variants, input) {
+        let data = variant(context, cache, input, prefixes, base, &mut lookups)?.map(|x| vec![x]);
+        cache.insert(input.to_string(), data.clone(), lookups);
+        return Ok(data);
     }
 
     let mut variables = HashMap::new();
-    let mut tokens = Arc::new(Vec::new());
-    let mut returns_iter = None;
+    let mut rule = context.consts.get(input);
 
-    if let Some(x) = consts.get(input) {
-        tokens = Arc::new(x.to_vec());
-        returns_iter = Some(x.iter());
-    } else {
-        for i in parsed.iter() {
+    if rule.is_none() {
+        if let Some(i) = candidates(context, input).first() {
             if let Some(caps) = i.0.regex.captures(input) {
-                let mut caps_iter = caps.iter();
-                caps_iter.next();
-                for x in i.0.variables.iter() {
-                    variables.insert(
-                        x.clone(),
-                        caps_iter
-                            .next()
-                            .unwrap_or_else(|| {
-                                panic!("Internal Error\ninput: {input}\nregex: {:?}", i.0.regex)
-                            })
-                            .unwrap_or_else(|| {
-                                panic!("Internal Error\ninput: {input}\nregex: {}", i.0.regex)
-                            })
-                            .as_str()
-                            .to_string(),
-                    );
+                for (x, value) in i.0.variables.iter().zip(caps.iter().skip(1)) {
+                    variables.insert(x.clone(), value.map_or("", |x| x.as_str()).to_string());
                 }
-                returns_iter = Some(i.1.iter());
-                tokens = Arc::new(i.1.clone());
-                break;
             }
+            record(context, |x| {
+                x.variables =
+                    i.0.variables
+                        .iter()
+                        .map(|x| (x.clone(), variables[x].clone()))
+                        .collect()
+            });
+            rule = Some(&i.1);
         }
     }
 
-    let variables = Arc::new(variables);
-
-    if let Some(mut returns_iter) = returns_iter {
-        let mut returns = String::new();
-
-        while let Some(x) = returns_iter.next() {
-            let is_replacement = x == &Token::FullReplacementStart;
-            let adds = match x {
-                Token::FullReplacementStart => full_replacement(
-                    parsed.clone(),
-                    consts.clone(),
-                    cache,
-                    &mut returns_iter,
-                    variables.clone(),
-                    tokens.clone(),
-                )?,
-                Token::ReplacementStart => {
-                    replacement(&mut returns_iter, variables.clone(), tokens.clone())
-                }
-                Token::Add => "+".to_string(),
-                Token::Subtract => r"\-".to_string(),
-                Token::Times => r"\*".to_string(),
-                Token::Divide => "/".to_string(),
-                Token::Number(x) => x.to_string(),
-                Token::String(x) => x.clone(),
-                Token::SemiColon => ";".to_string(),
-                Token::NewLine => ";".to_string(),
-                Token::Space => " ".to_string(),
-                Token::MultiEqualStart => "{".to_string(),
-                Token::MultiEqualEnd => "}".to_string(),
-                _ => "".to_string(),
-            };
-            if is_replacement && (adds.starts_with('.') || adds.starts_with('@')) {
-                returns = format!("{adds}{returns}");
-            } else {
-                returns.push_str(adds.as_str());
+    if let Some(rule) = rule {
+        record(context, |x| x.rule = Some(rule.clone()));
+        let data = evaluate(
+            context,
+            cache,
+            input,
+            rule,
+            &rule.body,
+            &variables,
+            &mut lookups,
+        )?;
+        cache.insert(input.to_string(), data.clone(), lookups);
+        return Ok(data);
+    }
+    cache.insert(input.to_string(), None, lookups);
+    Ok(None)
+}
+
+/// Where the text of a definition is written
+fn origin(rule: &Rule) -> Origin {
+    Origin {
+        file: rule.file.clone(),
+        line: rule.line,
+    }
+}
+
+/// The blocks a definition is made into, the statement being written is kept open until a `;` so
+/// that what a variable or a lookup gives is added to it
+#[derive(Default)]
+struct Builder {
+    blocks: Vec<Block>,
+    statement: String,
+    /// Where the statement started being written
+    origin: Option<Origin>,
+}
+
+impl Builder {
+    /// Adds text written in a definition, each `;` ending the statement
+    fn text(&mut self, text: &str, origin: &Origin) {
+        for (i, x) in text.split(';').enumerate() {
+            if i != 0 {
+                self.end();
             }
+            self.value(x, origin);
         }
-        if !returns.ends_with(';') {
-            returns.push(';');
-        }
-        let data = returns
-            .trim()
-            .to_string()
-            .replace("; ", ";")
-            .replace(": ", ":")
-            .replace(" {", "{")
-            .replace("{ ", "{");
-        cache.insert(input.to_string(), Some(data.clone()));
-        return Some(data);
     }
-    cache.insert(input.to_string(), None);
-    None
-}
 
-#[inline(always)]
-fn full_replacement(
-    parsed: Arc<Vec<(Name, Vec<Token>)>>,
-    consts: Arc<HashMap<String, Vec<Token>>>,
-    cache: &mut HashMap<String, Option<String>>,
-    returns_iter: &mut Iter<Token>,
-    variables: Arc<HashMap<String, String>>,
-    i: Arc<Vec<Token>>,
-) -> Option<String> {
-    let mut search = String::new();
-    let mut y = 1;
-    while let Some(x) = returns_iter.next() {
-        if x == &Token::FullReplacementStart {
-            y += 1;
+    /// Adds to the statement without ending it, so a `;` in what a variable captured is kept
+    fn value(&mut self, value: &str, origin: &Origin) {
+        if self.statement.trim().is_empty() {
+            self.statement.clear();
+            self.origin = Some(origin.clone());
         }
-        if x == &Token::FullReplacementEnd {
-            if y == 1 {
-                break;
-            } else {
-                y -= 1;
+        self.statement.push_str(value);
+    }
+
+    /// Adds the blocks of a class that was looked up, its first declaration going on the end of
+    /// the statement and its last one being kept open
+    fn lookup(&mut self, blocks: Vec<Block>) {
+        for (i, block) in blocks.into_iter().enumerate() {
+            match block {
+                Block::Declaration(text, origin) => {
+                    if i != 0 {
+                        self.end();
+                    }
+                    if self.statement.trim().is_empty() {
+                        self.statement.clear();
+                        self.origin = origin;
+                    }
+                    self.statement.push_str(&text);
+                }
+                block => {
+                    self.end();
+                    self.blocks.push(block);
+                }
             }
         }
-        search.push_str(&match x {
-            Token::ReplacementStart => replacement(returns_iter, variables.clone(), i.clone()),
-            Token::Add => "+".to_string(),
-            Token::Subtract => r"\-".to_string(),
-            Token::Times => r"\*".to_string(),
-            Token::Divide => "/".to_string(),
-            Token::Number(x) => x.to_string(),
-            Token::String(x) => x.clone(),
-            Token::SemiColon => ";".to_string(),
-            Token::NewLine => ";".to_string(),
-            Token::FullReplacementStart => full_replacement(
-                parsed.clone(),
-                consts.clone(),
-                cache,
-                returns_iter,
-                variables.clone(),
-                i.clone(),
-            )?,
-            _ => "".to_string(),
-        });
-    }
-    let returns = converts(parsed, consts, cache, search)?;
-    let mut chars = returns.chars();
-    chars.next_back();
-    Some(chars.as_str().to_string())
+    }
+
+    /// Ends the statement as a declaration if anything was written in it
+    fn end(&mut self) {
+        let statement = std::mem::take(&mut self.statement);
+        let origin = self.origin.take();
+        if !statement.trim().is_empty() {
+            self.blocks.push(Block::declaration(statement, origin));
+        }
+    }
+
+    /// Ends the statement as the selector or at-rule of a `{{ }}`
+    fn block(&mut self, blocks: Vec<Block>) {
+        let prelude = std::mem::take(&mut self.statement);
+        self.origin = None;
+        self.blocks.push(Block::rule(prelude, blocks));
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        self.end();
+        self.blocks
+    }
 }
 
+/// Makes the nodes of a definition into blocks, nothing is given if it looks up a class that does
+/// not exist
 #[inline(always)]
-fn replacement(
-    returns_iter: &mut Iter<Token>,
-    variables: Arc<HashMap<String, String>>,
-    i: Arc<Vec<Token>>,
-) -> String {
-    let mut next = returns_iter
-        .next()
-        .expect("Should never happen but failed to get value");
-    if next == &Token::Space {
-        next = returns_iter
-            .next()
-            .expect("Should never happen but failed to get value");
-    }
-    if next == &Token::ReplacementEnd {
-        (*variables
-            .get("")
-            .unwrap_or_else(|| panic!("Internal Error\nCould not find variable \"\" in {i:?}")))
-        .clone()
-    } else {
-        let mut next_value = false;
-        let value = match next {
-            Token::String(w) => (*variables.get(w).unwrap_or_else(|| {
-                panic!("Internal Error\nCould not find variable \"{w}\" in {i:?}")
-            }))
-            .to_string(),
-            Token::Number(n) => n.to_string(),
-            Token::Add => {
-                next_value = true;
-                (*variables.get("").unwrap_or_else(|| {
-                    panic!("Internal Error\nCould not find variable \"\" in {i:?}")
-                }))
-                .to_string()
-            }
-            Token::Subtract => {
-                next_value = true;
-                (*variables.get("").unwrap_or_else(|| {
-                    panic!("Internal Error\nCould not find variable \"\" in {i:?}")
-                }))
-                .to_string()
-            }
-            Token::Times => {
-                next_value = true;
-                (*variables.get("").unwrap_or_else(|| {
-                    panic!("Internal Error\nCould not find variable \"\" in {i:?}")
-                }))
-                .to_string()
+fn evaluate(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+    rule: &Rule,
+    nodes: &[Node],
+    variables: &HashMap<String, String>,
+    lookups: &mut Vec<String>,
+) -> Result<Option<Vec<Block>>, Box<ConvertError>> {
+    let mut blocks = Builder::default();
+    let origin = origin(rule);
+    for node in nodes {
+        match &node.kind {
+            NodeKind::Text(x) => blocks.text(x, &origin),
+            NodeKind::Variable(x) => {
+                blocks.value(variables.get(x).map_or("", |x| x.as_str()), &origin)
             }
-            Token::Divide => {
-                next_value = true;
-                (*variables.get("").unwrap_or_else(|| {
-                    panic!("Internal Error\nCould not find variable \"\" in {i:?}")
-                }))
-                .to_string()
-            }
-            _ => panic!("Internal Error\nDont know what {next:?} is in {i:?}"),
-        };
-        if returns_iter.len() > 0 {
-            let mut int_value = value.parse::<f64>().unwrap_or_else(|_| {
-                panic!("Internal Error\nCant do multipul things to a string, \"{value}\", in {i:?}")
-            });
-            if next_value {
-                let next_value = match returns_iter
-                    .next()
-                    .expect("Internal Error\nCould nothing after a \"+\" \"-\" \"*\" \"/\"")
-                {
-                    Token::String(w) => variables
-                        .get(w)
-                        .unwrap_or_else(|| {
-                            panic!("Internal Error\nCould not find variable \"{w}\" in {i:?}")
-                        })
-                        .parse::<f64>()
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "Internal Error\nCould not convert variable \"{w}\" in {i:?} to f64"
-                            )
-                        }),
-                    Token::Number(w) => *w,
-                    _ => panic!("Internal Error\nCould not find out what char is requested for"),
-                };
-                match next {
-                    Token::Add => int_value += next_value,
-                    Token::Subtract => int_value -= next_value,
-                    Token::Times => int_value *= next_value,
-                    Token::Divide => int_value /= next_value,
-                    _ => panic!("Internal Error\nUsed a token not able to use in replacement"),
+            NodeKind::Expression(x, default) => blocks.value(
+                &expression(context, input, rule, node, x, default, variables)?,
+                &origin,
+            ),
+            NodeKind::Lookup(x) => {
+                match lookup(context, cache, input, rule, x, variables, lookups)? {
+                    Some(x) => blocks.lookup(x),
+                    None => return Ok(None),
                 }
             }
-            while let Some(mut y) = returns_iter.next() {
-                if y == &Token::Space {
-                    y = returns_iter
-                        .next()
-                        .expect("Inetrnal Error\nCould not find end of Replacement");
+            NodeKind::Block(x) => {
+                match evaluate(context, cache, input, rule, x, variables, lookups)? {
+                    Some(x) => blocks.block(x),
+                    None => return Ok(None),
                 }
-                if y == &Token::ReplacementEnd {
-                    break;
-                }
-                let mut next_value = returns_iter
-                    .next()
-                    .expect("Internal Error\nCould nothing after a \"+\" \"-\" \"*\" \"/\"");
-                if next_value == &Token::Space {
-                    next_value = returns_iter
-                        .next()
-                        .expect("Inetrnal Error\nCould not find end of Replacement");
+            }
+        }
+    }
+    Ok(Some(blocks.finish()))
+}
+
+/// What some nodes are as text, for the name of a class that is looked up and the selector or
+/// at-rule of a variant, nothing is given if it looks up a class that does not exist
+fn text(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+    rule: &Rule,
+    nodes: &[Node],
+    variables: &HashMap<String, String>,
+    lookups: &mut Vec<String>,
+) -> Result<Option<String>, Box<ConvertError>> {
+    let mut text = String::new();
+    for node in nodes {
+        match &node.kind {
+            NodeKind::Text(x) => text.push_str(x),
+            NodeKind::Variable(x) => text.push_str(variables.get(x).map_or("", |x| x.as_str())),
+            NodeKind::Expression(x, default) => text.push_str(&expression(
+                context, input, rule, node, x, default, variables,
+            )?),
+            NodeKind::Lookup(x) => {
+                match lookup(context, cache, input, rule, x, variables, lookups)? {
+                    Some(x) => text.push_str(&inline(&x)),
+                    None => return Ok(None),
                 }
-                let next = match next_value {
-                    Token::String(w) => variables
-                        .get(w)
-                        .unwrap_or_else(|| {
-                            panic!("Internal Error\nCould not find variable \"{w}\" in {i:?}")
-                        })
-                        .parse::<f64>()
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "Internal Error\nCould not convert variable \"{w}\" in {i:?} to f64"
-                            )
-                        }),
-                    Token::Number(w) => *w,
-                    _ => panic!(
-                        "Internal Error\nCould not find out what char is requested for {y:?}"
-                    ),
-                };
-                match y {
-                    Token::Add => int_value += next,
-                    Token::Subtract => int_value -= next,
-                    Token::Times => int_value *= next,
-                    Token::Divide => int_value /= next,
-                    _ => panic!("Internal Error\nUsed a token not able to use in replacement"),
+            }
+            NodeKind::Block(x) => {
+                match self::text(context, cache, input, rule, x, variables, lookups)? {
+                    Some(x) => text.push_str(&format!("{{{x}}}")),
+                    None => return Ok(None),
                 }
             }
-            int_value.to_string()
+        }
+    }
+    Ok(Some(text))
+}
+
+/// The result of some arithmetic, with the unit written after it if it does not have one
+fn expression(
+    context: &Context,
+    input: &str,
+    rule: &Rule,
+    node: &Node,
+    expression: &ast::Expr,
+    default: &Option<String>,
+    variables: &HashMap<String, String>,
+) -> Result<String, Box<ConvertError>> {
+    let precision = context.options.precision;
+    let value = expression
+        .evaluate(variables)
+        .map_err(|x| Box::new(ConvertError::new(input, rule, node, x)))?;
+    Ok(match (value, default) {
+        (Value::Dimension(x, unit), Some(default)) if unit.is_empty() => {
+            format!("{:.precision$}", Value::Dimension(x, default.clone()))
+        }
+        (value, _) => format!("{value:.precision$}"),
+    })
+}
+
+/// The blocks of the class a `{{{ }}}` looks up, nothing if it does not exist
+fn lookup(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+    rule: &Rule,
+    nodes: &[Node],
+    variables: &HashMap<String, String>,
+    lookups: &mut Vec<String>,
+) -> Result<Option<Vec<Block>>, Box<ConvertError>> {
+    let Some(search) = text(context, cache, input, rule, nodes, variables, lookups)? else {
+        return Ok(None);
+    };
+    let search = search.split_whitespace().collect::<String>();
+    lookups.push(search.clone());
+    converts(context, cache, &search).map_err(|x| x.within(input))
+}
+
+/// The blocks of a class that was looked up as text, its declarations being joined by `;`
+fn inline(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(|x| match x {
+            Block::Declaration(x, _) => x.clone(),
+            x => css::render(std::slice::from_ref(x), css::Format::Minify),
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Splits the leading `variant:` prefixes off of a class, stopping at the first prefix that is not
+/// a defined variant, a class using the same variant twice is not split
+#[inline(always)]
+pub fn split_variants<'a>(
+    variants: &HashMap<String, Rule>,
+    input: &'a str,
+) -> Option<(Vec<&'a str>, &'a str)> {
+    let mut prefixes = Vec::new();
+    let mut base = input;
+    while let Some((prefix, rest)) = base.split_once(':') {
+        if !variants.contains_key(prefix) {
+            break;
+        }
+        if prefixes.contains(&prefix) {
+            return None;
+        }
+        prefixes.push(prefix);
+        base = rest;
+    }
+    if prefixes.is_empty() || base.is_empty() {
+        None
+    } else {
+        Some((prefixes, base))
+    }
+}
+
+/// Converts the base class and wraps it in the selector and at-rules of each variant, the first
+/// variant being the outer most
+#[inline(always)]
+fn variant(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+    prefixes: Vec<&str>,
+    base: &str,
+    lookups: &mut Vec<String>,
+) -> Result<Option<Block>, Box<ConvertError>> {
+    lookups.push(base.to_string());
+    let Some(blocks) = converts(context, cache, base).map_err(|x| x.within(input))? else {
+        return Ok(None);
+    };
+    let Some((selector, at_rules)) = wrappers(context, cache, input, &prefixes, lookups)? else {
+        return Ok(None);
+    };
+    let mut block = Block::Rule(selector, blocks);
+    for at_rule in at_rules.into_iter().rev() {
+        block = Block::AtRule(at_rule, vec![block]);
+    }
+    Ok(Some(block))
+}
+
+/// The selector of a class and the at-rules it goes in from the variants it is prefixed with, the
+/// first at-rule being the outer most, nothing is given if a variant looks up a class that does
+/// not exist
+fn wrappers(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+    prefixes: &[&str],
+    lookups: &mut Vec<String>,
+) -> Result<Option<(String, Vec<String>)>, Box<ConvertError>> {
+    let mut selector = format!(".{}", css::escape(input));
+    let mut at_rules = Vec::new();
+    for prefix in prefixes {
+        let rule = &context.variants[*prefix];
+        record(context, |x| x.variants.push(rule.clone()));
+        let Some(wrapper) = text(
+            context,
+            cache,
+            input,
+            rule,
+            &rule.body,
+            &HashMap::new(),
+            lookups,
+        )?
+        else {
+            return Ok(None);
+        };
+        let wrapper = wrapper.trim().trim_end_matches(';').trim();
+        if wrapper.starts_with('@') {
+            at_rules.push(wrapper.to_string());
+        } else if wrapper.contains('&') {
+            selector = wrapper.replace('&', &selector);
         } else {
-            value
+            selector.push_str(wrapper);
         }
     }
-    .trim()
-    .to_string()
+    Ok(Some((selector, at_rules)))
+}
+
+/// The css of a class as a rule with its declarations, in the at-rules of its variants, each
+/// declaration keeps the definition it was written in
+pub fn style(
+    context: &Context,
+    cache: &Cache,
+    input: &str,
+) -> Result<Option<Block>, Box<ConvertError>> {
+    let Some(mut blocks) = converts(context, cache, input)? else {
+        return Ok(None);
+    };
+    // a class with variants is already the rule for it
+    if split_variants(context.variants, input).is_some() {
+        return Ok(blocks.pop());
+    }
+    Ok(Some(Block::Rule(
+        format!(".{}", css::escape(input)),
+        blocks,
+    )))
 }
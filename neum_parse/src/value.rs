@@ -0,0 +1,323 @@
+//! Numbers with CSS units that expressions are worked out with
+//! ```
+//! # use neum_parse::value::Value;
+//! let value = Value::parse("4px").unwrap();
+//! assert_eq!(value, Value::Dimension(4.0, String::from("px")));
+//! assert_eq!(value.to_string(), "4px");
+//! assert_eq!(Value::parse("abc"), None);
+//! ```
+
+use crate::ast::{Expr, Function, Operator};
+use crate::error::ConvertErrorKind;
+use std::fmt;
+
+/// The units of lengths, including percentages
+pub const LENGTHS: &[&str] = &[
+    "px", "rem", "em", "%", "vh", "vw", "vmin", "vmax", "svh", "svw", "lvh", "lvw", "dvh", "dvw",
+    "ch", "ex", "cap", "ic", "lh", "rlh", "cqw", "cqh", "cqi", "cqb", "cqmin", "cqmax", "pt", "pc",
+    "cm", "mm", "q", "in",
+];
+
+/// The units that are understood, grouped by what they measure, units in the same group can be
+/// added together with `calc()`
+const UNITS: &[&[&str]] = &[
+    LENGTHS,
+    &["deg", "rad", "grad", "turn"],
+    &["s", "ms"],
+    &["hz", "khz"],
+    &["dpi", "dpcm", "dppx", "x"],
+    &["fr"],
+];
+
+/// Gives the group that a unit is in, `None` if it is not a known unit
+fn group(unit: &str) -> Option<usize> {
+    UNITS
+        .iter()
+        .position(|x| x.iter().any(|x| x.eq_ignore_ascii_case(unit)))
+}
+
+/// Gives the unit at the start of some text if it is a known unit
+/// ```
+/// # use neum_parse::value::unit;
+/// assert_eq!(unit("rem;"), Some("rem"));
+/// assert_eq!(unit("%)"), Some("%"));
+/// assert_eq!(unit("remx"), None);
+/// ```
+pub fn unit(text: &str) -> Option<&str> {
+    let length = if text.starts_with('%') {
+        1
+    } else {
+        text.find(|x: char| !x.is_ascii_alphabetic())
+            .unwrap_or(text.len())
+    };
+    let unit = &text[..length];
+    group(unit).map(|_| unit)
+}
+
+/// How many decimals numbers are written with when no precision is given, enough for anything a
+/// browser will show while hiding float errors like `0.30000000000000004`
+pub const DEFAULT_PRECISION: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A number and its unit, the unit is empty for plain numbers
+    Dimension(f64, String),
+    /// Math on values whose units could not be reduced which is left for the browser to work out,
+    /// and the unit of the first value in it
+    Calc(Expr, String),
+}
+
+impl Value {
+    /// Reads a number with an optional unit, like `4`, `-0.5rem` or `50%`
+    pub fn parse<S: AsRef<str>>(value: S) -> Option<Value> {
+        let value = value.as_ref().trim();
+        let length = value
+            .find(|x: char| !(x.is_ascii_digit() || x == '.' || x == '-' || x == '+'))
+            .unwrap_or(value.len());
+        let number = value[..length].parse::<f64>().ok()?;
+        let unit = &value[length..];
+        if unit.is_empty() || group(unit).is_some() {
+            Some(Value::Dimension(number, unit.to_lowercase()))
+        } else {
+            None
+        }
+    }
+
+    /// Works out `self operator other`, keeping the unit, giving a `calc()` if the units can be
+    /// added together but not reduced and an error if they can not be used together
+    /// ```
+    /// # use neum_parse::{ast::Operator, value::Value};
+    /// let px = Value::parse("4px").unwrap();
+    /// let rem = Value::parse("1rem").unwrap();
+    /// let two = Value::parse("2").unwrap();
+    /// assert_eq!(px.apply(Operator::Times, &two).unwrap().to_string(), "8px");
+    /// assert_eq!(px.apply(Operator::Divide, &px).unwrap().to_string(), "1");
+    /// assert_eq!(px.apply(Operator::Add, &rem).unwrap().to_string(), "calc(4px + 1rem)");
+    /// assert!(px.apply(Operator::Times, &rem).is_err());
+    /// assert!(px.apply(Operator::Add, &two).is_err());
+    /// assert!(px.apply(Operator::Divide, &Value::parse("0").unwrap()).is_err());
+    /// ```
+    pub fn apply(&self, operator: Operator, other: &Value) -> Result<Value, ConvertErrorKind> {
+        self.apply_units(operator, other)?.finite(|| {
+            Expr::Binary(Box::new(self.expr()), operator, Box::new(other.expr())).to_string()
+        })
+    }
+
+    fn apply_units(&self, operator: Operator, other: &Value) -> Result<Value, ConvertErrorKind> {
+        use Value::*;
+        match (self, operator, other) {
+            (Dimension(x, unit), Operator::Add | Operator::Subtract, Dimension(y, other_unit))
+                if unit == other_unit =>
+            {
+                Ok(Dimension(
+                    match operator {
+                        Operator::Add => x + y,
+                        _ => x - y,
+                    },
+                    unit.clone(),
+                ))
+            }
+            (Dimension(x, unit), Operator::Times, Dimension(y, other_unit))
+                if unit.is_empty() || other_unit.is_empty() =>
+            {
+                Ok(Dimension(
+                    x * y,
+                    match unit.is_empty() {
+                        true => other_unit.clone(),
+                        false => unit.clone(),
+                    },
+                ))
+            }
+            (Dimension(x, unit), Operator::Divide, Dimension(y, other_unit))
+                if other_unit.is_empty() || unit == other_unit =>
+            {
+                Ok(Dimension(
+                    x / y,
+                    match other_unit.is_empty() {
+                        true => unit.clone(),
+                        false => String::new(),
+                    },
+                ))
+            }
+            (_, Operator::Add | Operator::Subtract, _)
+                if !self.unit().is_empty() && same_group(self.unit(), other.unit()) =>
+            {
+                Ok(self.calc(operator, other, self.unit()))
+            }
+            (Calc(_, unit), Operator::Times | Operator::Divide, Dimension(_, other_unit))
+                if other_unit.is_empty() =>
+            {
+                Ok(self.calc(operator, other, unit))
+            }
+            (Dimension(_, unit), Operator::Times, Calc(_, other_unit)) if unit.is_empty() => {
+                Ok(self.calc(operator, other, other_unit))
+            }
+            _ => Err(ConvertErrorKind::MixedUnits(
+                self.unit().to_string(),
+                other.unit().to_string(),
+            )),
+        }
+    }
+
+    /// Gives the value with its sign flipped
+    pub fn negate(&self) -> Value {
+        match self {
+            Value::Dimension(x, unit) => Value::Dimension(-x, unit.clone()),
+            Value::Calc(x, unit) => Value::Calc(Expr::Negate(Box::new(x.clone())), unit.clone()),
+        }
+    }
+
+    /// Works out a function on some values, `min`, `max` and `clamp` give the CSS function if the
+    /// units can not be compared
+    /// ```
+    /// # use neum_parse::{ast::Function, value::Value};
+    /// let values = [Value::parse("4.6px").unwrap(), Value::parse("1rem").unwrap()];
+    /// assert_eq!(Value::call(Function::Round, &values[..1]).unwrap().to_string(), "5px");
+    /// assert_eq!(Value::call(Function::Min, &values).unwrap().to_string(), "min(4.6px, 1rem)");
+    /// assert_eq!(Value::call(Function::Floor, &[values[0].clone(), Value::parse("2").unwrap()]).unwrap().to_string(), "4px");
+    /// assert!(Value::call(Function::Max, &[values[0].clone(), Value::parse("2").unwrap()]).is_err());
+    /// assert!(Value::call(Function::Round, &[values[0].clone(), Value::parse("0").unwrap()]).is_err());
+    /// ```
+    pub fn call(function: Function, arguments: &[Value]) -> Result<Value, ConvertErrorKind> {
+        match function {
+            Function::Round | Function::Floor | Function::Ceil | Function::Abs => {
+                let (x, unit) = match &arguments[0] {
+                    Value::Dimension(x, unit) => (*x, unit),
+                    value => {
+                        return Err(ConvertErrorKind::NotReducible(
+                            function.name().to_string(),
+                            value.to_string(),
+                        ))
+                    }
+                };
+                let step = match arguments.get(1) {
+                    Some(Value::Dimension(step, step_unit))
+                        if step_unit.is_empty() || step_unit == unit =>
+                    {
+                        *step
+                    }
+                    Some(value) => {
+                        return Err(ConvertErrorKind::MixedUnits(
+                            unit.clone(),
+                            value.unit().to_string(),
+                        ))
+                    }
+                    None => 1.0,
+                };
+                let x = match function {
+                    Function::Round => (x / step).round() * step,
+                    Function::Floor => (x / step).floor() * step,
+                    Function::Ceil => (x / step).ceil() * step,
+                    _ => x.abs(),
+                };
+                Value::Dimension(x, unit.clone()).finite(|| {
+                    Expr::Function(function, arguments.iter().map(Value::expr).collect())
+                        .to_string()
+                })
+            }
+            Function::Min | Function::Max | Function::Clamp => {
+                let unit = arguments[0].unit();
+                if let Some(x) = arguments.iter().find(|x| x.unit() != unit) {
+                    if unit.is_empty() || !same_group(unit, x.unit()) {
+                        return Err(ConvertErrorKind::MixedUnits(
+                            unit.to_string(),
+                            x.unit().to_string(),
+                        ));
+                    }
+                }
+                let numbers = arguments
+                    .iter()
+                    .map(|x| match x {
+                        Value::Dimension(x, other) if other == unit => Some(*x),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<f64>>>();
+                Ok(match numbers {
+                    Some(x) => Value::Dimension(
+                        match function {
+                            Function::Min => x.into_iter().fold(f64::INFINITY, f64::min),
+                            Function::Max => x.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                            _ => x[1].min(x[2]).max(x[0]),
+                        },
+                        unit.to_string(),
+                    ),
+                    None => Value::Calc(
+                        Expr::Function(function, arguments.iter().map(Value::expr).collect()),
+                        unit.to_string(),
+                    ),
+                })
+            }
+        }
+    }
+
+    /// The unit of the value, empty for plain numbers
+    pub fn unit(&self) -> &str {
+        match self {
+            Value::Dimension(_, unit) | Value::Calc(_, unit) => unit,
+        }
+    }
+
+    /// Gives the value as an expression so that it can be put in a `calc()`
+    fn expr(&self) -> Expr {
+        match self {
+            Value::Dimension(x, unit) if unit.is_empty() => Expr::Number(*x),
+            Value::Dimension(x, unit) => Expr::Dimension(*x, unit.clone()),
+            Value::Calc(x, _) => x.clone(),
+        }
+    }
+
+    /// Gives an error if the value is infinite or not a number, `expression` being what it was
+    /// worked out from
+    fn finite(self, expression: impl FnOnce() -> String) -> Result<Value, ConvertErrorKind> {
+        match self {
+            Value::Dimension(x, _) if !x.is_finite() => {
+                Err(ConvertErrorKind::NotFinite(expression()))
+            }
+            value => Ok(value),
+        }
+    }
+
+    fn calc(&self, operator: Operator, other: &Value, unit: &str) -> Value {
+        Value::Calc(
+            Expr::Binary(Box::new(self.expr()), operator, Box::new(other.expr())),
+            unit.to_string(),
+        )
+    }
+}
+
+/// If two units are known and measure the same thing
+fn same_group(unit: &str, other: &str) -> bool {
+    group(unit).is_some() && group(unit) == group(other)
+}
+
+/// Writes a number rounded to `precision` decimals without any trailing zeros
+/// ```
+/// # use neum_parse::value::number;
+/// assert_eq!(number(0.1 + 0.2, 6), "0.3");
+/// assert_eq!(number(100.0 / 3.0, 2), "33.33");
+/// assert_eq!(number(-0.0000001, 6), "0");
+/// ```
+pub fn number(x: f64, precision: usize) -> String {
+    let text = format!("{x:.precision$}");
+    let text = match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.'),
+        false => &text,
+    };
+    match text {
+        "-0" => "0".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Numbers are written with the precision given to the formatter, like `{:.2}`, or
+/// [`DEFAULT_PRECISION`] decimals
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let precision = f.precision().unwrap_or(DEFAULT_PRECISION);
+        match self {
+            Value::Dimension(x, unit) => write!(f, "{}{unit}", number(*x, precision)),
+            Value::Calc(x @ Expr::Function(..), _) => write!(f, "{x:.precision$}"),
+            Value::Calc(x, _) => write!(f, "calc({x:.precision$})"),
+        }
+    }
+}